@@ -1,8 +1,8 @@
 pub mod statement;
 pub mod expression;
 
-use crate::{ast::expression::Literal, lexer::{TextSpan, Token}};
-use statement::Statement;
+use crate::{ast::expression::Literal, lexer::{TextSpan, Token}, types::Type};
+use statement::{Parameter, Statement};
 use expression::Expression;
 
 pub struct Ast {
@@ -24,6 +24,46 @@ impl Ast {
     pub fn statements(&self) -> &Vec<Statement> {
         &self.statements
     }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Statement> {
+        self.statements.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Statement> {
+        self.statements.get(index)
+    }
+}
+
+impl Default for Ast {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> IntoIterator for &'a Ast {
+    type Item = &'a Statement;
+    type IntoIter = std::slice::Iter<'a, Statement>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.statements.iter()
+    }
+}
+
+impl IntoIterator for Ast {
+    type Item = Statement;
+    type IntoIter = std::vec::IntoIter<Statement>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.statements.into_iter()
+    }
 }
 
 pub trait AstExplorer {
@@ -39,40 +79,67 @@ pub trait AstExplorer {
         self.visit_statement_impl(statement);
     }
 
+    // Called around every statement, nested ones included, for passes like tracing or profiling.
+    // They fire from `visit_statement_impl`, so an override of `visit_statement` keeps them as long as it calls it.
+    fn statement_on_enter(&mut self, _statement: &Statement) {
+    }
+
+    fn statement_on_exit(&mut self, _statement: &Statement) {
+    }
+
     fn visit_statement_impl(&mut self, statement: &Statement) {
+        self.statement_on_enter(statement);
+        self.walk_statement(statement);
+        self.statement_on_exit(statement);
+    }
+
+    fn walk_statement(&mut self, statement: &Statement) {
         match statement {
-            Statement::VariableDeclaration { name, value } => {
-                                                                self.visit_variable_declaration(name, value);
+            Statement::VariableDeclaration { name, type_annotation, value, .. } => {
+                                                                self.visit_variable_declaration(name, type_annotation.as_ref(), value);
                                                             }
-            Statement::VariableAssignment { name, value } => {
+            Statement::ConstDeclaration { name, value, .. } => {
+                                                                self.visit_const_declaration(name, value);
+                                                            }
+            Statement::VariableAssignment { name, value, .. } => {
                                                                 self.visit_variable_assignement(name, value);
                                                             }
-            Statement::IfStatement { if_then_branch: if_then_block, else_branch } 
+            Statement::SwapStatement { left, right, .. } => self.visit_swap_statement(left, right),
+            Statement::IfStatement { if_then_branch: if_then_block, else_branch, .. }
                                                         => self.visit_if_statement(&if_then_block.condition, &if_then_block.then_branch, else_branch.as_ref().map(|b| &**b)),
-            Statement::BlockStatement { statements } => {
+            Statement::BlockStatement { statements, .. } => {
                                                         self.block_statement_on_enter();
                                                         statements.iter().for_each(|s: &Statement| self.visit_statement(s));
                                                         self.block_statement_on_exit();
                                                     }
-            Statement::WhileStatement { condition, body } => 
+            Statement::WhileStatement { condition, body, .. } => 
                                                     self.visit_while_statement(condition, body),
-            Statement::ForStatement { variable, start, end, step, body } => 
-                                        self.visit_for_statement(variable, start, end, step, body),
-            Statement::FunctionDefinition { name, arguments, body } => 
-                                        self.visit_function_definition(name, arguments, body),
+            Statement::ForStatement { variable, retains_variable, start, end, step, body, .. } => 
+                                        self.visit_for_statement(variable, *retains_variable, start, end, step, body),
+            Statement::RepeatStatement { count, body, .. } =>
+                                        self.visit_repeat_statement(count, body),
+            Statement::WhenStatement { subject, arms, default, .. } =>
+                                        self.visit_when_statement(subject, arms, default.as_deref()),
+            Statement::FunctionDefinition { name, arguments, return_type, body, .. } => 
+                                        self.visit_function_definition(name, arguments, return_type.as_ref(), body),
             Statement::FunctionCall(function_call_data) =>
                                         self.visit_function_call(&function_call_data.function_name, &function_call_data.arguments),
+            Statement::ExpressionStatement(expression) => self.visit_expression(expression),
             Statement::ReturnStatement { span, expression } => 
                     self.visit_return_statement(span.clone(), expression),
         }
     }
 
-    fn visit_variable_declaration(&mut self, name: &Token, value: &Expression);
+    fn visit_variable_declaration(&mut self, name: &Token, type_annotation: Option<&Type>, value: &Expression);
+    fn visit_const_declaration(&mut self, name: &Token, value: &Expression);
     fn visit_variable_assignement(&mut self, name: &Token, value: &Expression);
+    fn visit_swap_statement(&mut self, left: &Token, right: &Token);
     fn visit_if_statement(&mut self, condition: &Expression, then_branch: &Statement, else_branch: Option<&Statement>);
     fn visit_while_statement(&mut self, condition: &Expression, body: &Statement);
-    fn visit_for_statement(&mut self, variable: &Token, start: &Expression, end: &Expression, step: &Option<Expression>, body: &Statement);
-    fn visit_function_definition(&mut self, name: &Token, arguments: &[Token], body: &Statement);
+    fn visit_for_statement(&mut self, variable: &Token, retains_variable: bool, start: &Expression, end: &Expression, step: &Option<Expression>, body: &Statement);
+    fn visit_repeat_statement(&mut self, count: &Expression, body: &Statement);
+    fn visit_when_statement(&mut self, subject: &Expression, arms: &[(Expression, Statement)], default: Option<&Statement>);
+    fn visit_function_definition(&mut self, name: &Token, arguments: &[Parameter], return_type: Option<&Type>, body: &Statement);
     fn visit_function_call(&mut self, function_name: &Token, arguments: &[Expression]);
     fn visit_return_statement(&mut self, span: TextSpan, expression: &Option<Expression>);
 
@@ -82,6 +149,10 @@ pub trait AstExplorer {
     
 
     fn visit_expression(&mut self, expression: &Expression) {
+        self.visit_expression_impl(expression);
+    }
+
+    fn visit_expression_impl(&mut self, expression: &Expression) {
         match expression {
             Expression::Literal{value: Literal::Number(value), ..} => self.visit_number_expression(*value),
             Expression::Literal{value: Literal::Boolean(value), ..} => self.visit_boolean_expression(*value),
@@ -92,6 +163,11 @@ pub trait AstExplorer {
                                     self.visit_unary_operation(operator, operand),
             Expression::Grouped(expression) => self.visit_expression(expression),
             Expression::FunctionCall(function_call_data) => self.visit_function_call(&function_call_data.function_name, &function_call_data.arguments),
+            Expression::List { elements, .. } => self.visit_list_expression(elements),
+            Expression::Index { target, index, .. } => self.visit_index_expression(target, index),
+            Expression::Conditional { condition, then_expr, else_expr, .. } =>
+                                    self.visit_conditional_expression(condition, then_expr, else_expr),
+            Expression::Error(_) => self.visit_error_expression(),
         }
     }
     
@@ -100,4 +176,8 @@ pub trait AstExplorer {
     fn visit_variable_expression(&mut self, name: &Token);
     fn visit_binary_operation(&mut self, left: &Expression, operator: &expression::BinaryOperator, right: &Expression);
     fn visit_unary_operation(&mut self, operator: &expression::UnaryOperator, operand: &Expression);
+    fn visit_list_expression(&mut self, elements: &[Expression]);
+    fn visit_index_expression(&mut self, target: &Expression, index: &Expression);
+    fn visit_conditional_expression(&mut self, condition: &Expression, then_expr: &Expression, else_expr: &Expression);
+    fn visit_error_expression(&mut self);
 }
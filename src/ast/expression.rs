@@ -31,6 +31,28 @@ pub enum Expression {
     Grouped(Box<Expression>),
 
     FunctionCall(FunctionCallData),
+
+    List {
+        elements: Vec<Expression>,
+        span: crate::lexer::TextSpan,
+    },
+
+    Index {
+        target: Box<Expression>,
+        index: Box<Expression>,
+        span: crate::lexer::TextSpan,
+    },
+
+    /// `if condition then a else b`, evaluating only the chosen branch
+    Conditional {
+        condition: Box<Expression>,
+        then_expr: Box<Expression>,
+        else_expr: Box<Expression>,
+        span: crate::lexer::TextSpan,
+    },
+
+    /// Placeholder for an expression that failed to parse
+    Error(crate::lexer::TextSpan),
 }
 
 impl Expression {
@@ -42,6 +64,10 @@ impl Expression {
             Expression::UnaryOperation { operand, .. } => operand.span(),
             Expression::Grouped(expression) => expression.span(),
             Expression::FunctionCall(data) => data.function_name.span(),
+            Expression::List { span, .. } => span.clone(),
+            Expression::Index { span, .. } => span.clone(),
+            Expression::Conditional { span, .. } => span.clone(),
+            Expression::Error(span) => span.clone(),
         }
     }
 }
@@ -60,6 +86,7 @@ pub enum BinaryOperator {
     Multiply,
     Divide,
     Modulus,
+    Power,
 
     /// Comparison Operators
     Equal,
@@ -68,6 +95,12 @@ pub enum BinaryOperator {
     GreaterThan,
     LessThanOrEqual,
     GreaterThanOrEqual,
+    /// Bitwise Operators
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
     /// Logical Operators
     And,
     Or,
@@ -81,12 +114,18 @@ impl fmt::Display for BinaryOperator {
             BinaryOperator::Multiply => "*",
             BinaryOperator::Divide => "/",
             BinaryOperator::Modulus => "%",
+            BinaryOperator::Power => "**",
             BinaryOperator::Equal => "==",
             BinaryOperator::NotEqual => "!=",
             BinaryOperator::LessThan => "<",
             BinaryOperator::GreaterThan => ">",
             BinaryOperator::LessThanOrEqual => "<=",
             BinaryOperator::GreaterThanOrEqual => ">=",
+            BinaryOperator::BitAnd => "band",
+            BinaryOperator::BitOr => "bor",
+            BinaryOperator::BitXor => "bxor",
+            BinaryOperator::ShiftLeft => "shl",
+            BinaryOperator::ShiftRight => "shr",
             BinaryOperator::And => "and",
             BinaryOperator::Or => "or",
         };
@@ -109,15 +148,28 @@ impl BinaryOperator {
             | BinaryOperator::LessThanOrEqual
             | BinaryOperator::GreaterThanOrEqual => 2,
 
+            // Bitwise operators
+            BinaryOperator::BitOr => 3,
+            BinaryOperator::BitXor => 4,
+            BinaryOperator::BitAnd => 5,
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => 6,
+
             // Arithmetic operators
-            BinaryOperator::Add | BinaryOperator::Subtract => 3,
+            BinaryOperator::Add | BinaryOperator::Subtract => 7,
 
               BinaryOperator::Multiply 
             | BinaryOperator::Divide 
             | BinaryOperator::Modulus
-            => 4,
+            => 8,
+
+            BinaryOperator::Power => 9,
         }
     }
+
+    // `2 ** 3 ** 2` is `2 ** (3 ** 2)`, every other operator groups from the left
+    pub fn is_right_associative(&self) -> bool {
+        matches!(self, BinaryOperator::Power)
+    }
 }
 
 
@@ -131,6 +183,7 @@ impl TryFrom<crate::lexer::TokenKind> for BinaryOperator {
             crate::lexer::TokenKind::Star => Ok(BinaryOperator::Multiply),
             crate::lexer::TokenKind::Slash => Ok(BinaryOperator::Divide),
             crate::lexer::TokenKind::Percent => Ok(BinaryOperator::Modulus),
+            crate::lexer::TokenKind::StarStar => Ok(BinaryOperator::Power),
             crate::lexer::TokenKind::EqualEqual => Ok(BinaryOperator::Equal),
             crate::lexer::TokenKind::NotEqual => Ok(BinaryOperator::NotEqual),
             crate::lexer::TokenKind::LessThan => Ok(BinaryOperator::LessThan),
@@ -139,6 +192,11 @@ impl TryFrom<crate::lexer::TokenKind> for BinaryOperator {
             crate::lexer::TokenKind::GreaterThanOrEqual => Ok(BinaryOperator::GreaterThanOrEqual),
             crate::lexer::TokenKind::AndKeyword => Ok(BinaryOperator::And),
             crate::lexer::TokenKind::OrKeyword => Ok(BinaryOperator::Or),
+            crate::lexer::TokenKind::BandKeyword => Ok(BinaryOperator::BitAnd),
+            crate::lexer::TokenKind::BorKeyword => Ok(BinaryOperator::BitOr),
+            crate::lexer::TokenKind::BxorKeyword => Ok(BinaryOperator::BitXor),
+            crate::lexer::TokenKind::ShlKeyword => Ok(BinaryOperator::ShiftLeft),
+            crate::lexer::TokenKind::ShrKeyword => Ok(BinaryOperator::ShiftRight),
             _ => Err(()),
         }
     }
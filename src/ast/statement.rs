@@ -1,51 +1,130 @@
-use crate::{ast::expression::FunctionCallData, lexer::{TextSpan, Token}};
+use crate::{ast::expression::FunctionCallData, lexer::{TextSpan, Token}, types::Type};
 
 use super::expression::Expression;
 
 #[derive(Debug, Clone)]
 pub enum Statement {
+    // `keyword_span` is the span of the keyword that opens the statement, e.g. `let` or `while`
     VariableDeclaration {
+        keyword_span: TextSpan,
+        name: Token,
+        type_annotation: Option<Type>,
+        value: Expression,
+    },
+
+    ConstDeclaration {
+        keyword_span: TextSpan,
         name: Token,
         value: Expression,
     },
 
     VariableAssignment {
+        keyword_span: TextSpan,
         name: Token,
         value: Expression,
     },
 
+    SwapStatement {
+        keyword_span: TextSpan,
+        left: Token,
+        right: Token,
+    },
+
     IfStatement {
+        keyword_span: TextSpan,
         if_then_branch: IfThenBranch,
         else_branch: Option<Box<Statement>>,
     },
 
     BlockStatement {
         statements: Vec<Statement>,
+        span: TextSpan,
     },
-    WhileStatement { condition: Expression, body: Box<Statement> },
+    WhileStatement { keyword_span: TextSpan, condition: Expression, body: Box<Statement> },
 
     ForStatement {
+        keyword_span: TextSpan,
         variable: Token,
+        // `for let i ...` declares the variable in the enclosing scope so it outlives the loop
+        retains_variable: bool,
         start: Expression,
         end: Expression,
         step: Option<Expression>,
         body: Box<Statement>,
     },
 
+    RepeatStatement { keyword_span: TextSpan, count: Expression, body: Box<Statement> },
+
+    WhenStatement {
+        keyword_span: TextSpan,
+        subject: Expression,
+        arms: Vec<(Expression, Statement)>,
+        default: Option<Box<Statement>>,
+    },
+
     FunctionDefinition {
+        keyword_span: TextSpan,
         name: Token,
-        arguments: Vec<Token>,
+        arguments: Vec<Parameter>,
+        return_type: Option<Type>,
         body: Box<Statement>,
     },
 
     FunctionCall(FunctionCallData),
 
+    // An expression evaluated for its own sake, e.g. `(1 + 2) * 3`
+    ExpressionStatement(Expression),
+
     ReturnStatement {
         span: TextSpan,
         expression: Option<Expression>,
     },
 }
 
+impl Statement {
+    pub fn span(&self) -> TextSpan {
+        match self {
+            Statement::VariableDeclaration { keyword_span, value, .. }
+            | Statement::ConstDeclaration { keyword_span, value, .. }
+            | Statement::VariableAssignment { keyword_span, value, .. } => keyword_span.union(&value.span()),
+            Statement::SwapStatement { keyword_span, right, .. } => keyword_span.union(&right.span()),
+            Statement::IfStatement { keyword_span, if_then_branch, else_branch } => {
+                let span = keyword_span.union(&if_then_branch.then_branch.span());
+                match else_branch {
+                    Some(else_branch) => span.union(&else_branch.span()),
+                    None => span,
+                }
+            }
+            Statement::BlockStatement { span, .. } => span.clone(),
+            Statement::WhileStatement { keyword_span, body, .. }
+            | Statement::ForStatement { keyword_span, body, .. }
+            | Statement::RepeatStatement { keyword_span, body, .. }
+            | Statement::FunctionDefinition { keyword_span, body, .. } => keyword_span.union(&body.span()),
+            Statement::WhenStatement { keyword_span, subject, arms, default } => {
+                let last_body = default.as_deref().or(arms.last().map(|(_, body)| body));
+                keyword_span.union(&last_body.map_or(subject.span(), Statement::span))
+            }
+            Statement::FunctionCall(data) => data.function_name.span(),
+            Statement::ExpressionStatement(expression) => expression.span(),
+            Statement::ReturnStatement { span, expression } => match expression {
+                Some(expression) => span.union(&expression.span()),
+                None => span.clone(),
+            },
+        }
+    }
+
+    // `let` and `const` declarations, which still run before `main` to set up the globals
+    pub fn is_declaration(&self) -> bool {
+        matches!(self, Statement::VariableDeclaration { .. } | Statement::ConstDeclaration { .. })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    pub name: Token,
+    pub type_annotation: Option<Type>,
+}
+
 #[derive(Debug, Clone)]
 pub struct IfThenBranch {
     pub condition: Expression,
@@ -0,0 +1,334 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{ast::{expression::{BinaryOperator, Expression, Literal, UnaryOperator}, statement::Statement, Ast}, lexer::TextSpan};
+
+// Instructions of a stack machine: operands are popped from the stack and results pushed back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    /// Pushes the integer at the given index of the constants table
+    PushInt(usize),
+    PushBool(bool),
+    /// Pushes the value of a function that returns nothing
+    PushUnit,
+    Pop,
+
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+
+    Equal,
+    NotEqual,
+    LessThan,
+    GreaterThan,
+    LessThanOrEqual,
+    GreaterThanOrEqual,
+
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+
+    And,
+    Or,
+
+    Negate,
+    Not,
+
+    LoadVar(String),
+    /// Pops a value into a new variable of the innermost scope
+    DeclareVar(String),
+    /// Pops a value into the nearest variable with the given name
+    StoreVar(String),
+    EnterScope,
+    ExitScope,
+
+    /// Jumps to the instruction at the given index
+    Jump(usize),
+    JumpIfFalse(usize),
+    /// Calls a user function, or else a native one, with the given number of arguments, taken from the top of the stack
+    Call { name: String, arg_count: usize },
+    /// Returns from the current function with the value on top of the stack
+    Return,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bytecode {
+    pub instructions: Vec<Instruction>,
+    pub constants: Vec<i64>,
+    /// Span of the statement or expression each instruction was emitted for, at the instruction's index
+    pub spans: Vec<TextSpan>,
+    pub functions: BTreeMap<String, FunctionEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionEntry {
+    /// Index of the first instruction of the body
+    pub address: usize,
+    pub parameters: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodegenError {
+    Unsupported { construct: &'static str, span: TextSpan },
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenError::Unsupported { construct, span } =>
+                write!(f, "at {}:{}: {} cannot be compiled to bytecode yet", span.start.line, span.start.column, construct),
+        }
+    }
+}
+
+// Walks the AST and emits bytecode. Loops other than `while`, `when` statements, lists and indexing are not supported yet.
+pub struct Codegen {
+    instructions: Vec<Instruction>,
+    constants: Vec<i64>,
+    spans: Vec<TextSpan>,
+    functions: BTreeMap<String, FunctionEntry>,
+    // Spans of the statements and expressions being compiled, the innermost last
+    enclosing_spans: Vec<TextSpan>,
+}
+
+impl Codegen {
+    pub fn new() -> Self {
+        Codegen {
+            instructions: Vec::new(),
+            constants: Vec::new(),
+            spans: Vec::new(),
+            functions: BTreeMap::new(),
+            enclosing_spans: Vec::new(),
+        }
+    }
+
+    // Like the interpreter, a program defining `main` only runs its declarations before calling it
+    pub fn generate(ast: &Ast) -> Result<Bytecode, CodegenError> {
+        let mut codegen = Self::new();
+        let main = ast.statements().iter().find(|statement| matches!(statement, Statement::FunctionDefinition { name, .. } if name.value == "main"));
+
+        for statement in ast.statements() {
+            if main.is_none() || statement.is_declaration() || matches!(statement, Statement::FunctionDefinition { .. }) {
+                codegen.statement(statement)?;
+            }
+        }
+        if let Some(main) = main {
+            codegen.enclosing_spans.push(main.span());
+            codegen.emit(Instruction::Call { name: "main".to_string(), arg_count: 0 });
+            codegen.emit(Instruction::Pop);
+            codegen.enclosing_spans.pop();
+        }
+
+        Ok(Bytecode {
+            instructions: codegen.instructions,
+            constants: codegen.constants,
+            spans: codegen.spans,
+            functions: codegen.functions,
+        })
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        self.instructions.push(instruction);
+        self.spans.push(self.enclosing_spans.last().cloned().expect("Instructions are emitted for a statement or an expression"));
+    }
+
+    // Reserves the slot of a jump whose target is not known yet, returning its index to patch later
+    fn emit_placeholder(&mut self) -> usize {
+        self.emit(Instruction::Jump(0));
+        self.instructions.len() - 1
+    }
+
+    // Equal integers share one slot of the constants table
+    fn constant(&mut self, value: i64) -> usize {
+        match self.constants.iter().position(|constant| *constant == value) {
+            Some(index) => index,
+            None => {
+                self.constants.push(value);
+                self.constants.len() - 1
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), CodegenError> {
+        self.enclosing_spans.push(statement.span());
+        let result = self.statement_instructions(statement);
+        self.enclosing_spans.pop();
+        result
+    }
+
+    fn statement_instructions(&mut self, statement: &Statement) -> Result<(), CodegenError> {
+        match statement {
+            Statement::VariableDeclaration { name, value, .. }
+            | Statement::ConstDeclaration { name, value, .. } => {
+                self.expression(value)?;
+                self.emit(Instruction::DeclareVar(name.value.clone()));
+            }
+            Statement::VariableAssignment { name, value, .. } => {
+                self.expression(value)?;
+                self.emit(Instruction::StoreVar(name.value.clone()));
+            }
+            // Both values are on the stack before either variable is overwritten
+            Statement::SwapStatement { left, right, .. } => {
+                self.emit(Instruction::LoadVar(left.value.clone()));
+                self.emit(Instruction::LoadVar(right.value.clone()));
+                self.emit(Instruction::StoreVar(left.value.clone()));
+                self.emit(Instruction::StoreVar(right.value.clone()));
+            }
+            Statement::FunctionCall(data) => {
+                self.function_call(&data.function_name.value, &data.arguments)?;
+                self.emit(Instruction::Pop);
+            }
+            Statement::ExpressionStatement(expression) => {
+                self.expression(expression)?;
+                self.emit(Instruction::Pop);
+            }
+            Statement::BlockStatement { statements, .. } => {
+                self.emit(Instruction::EnterScope);
+                for statement in statements {
+                    self.statement(statement)?;
+                }
+                self.emit(Instruction::ExitScope);
+            }
+            Statement::IfStatement { if_then_branch, else_branch, .. } => {
+                self.expression(&if_then_branch.condition)?;
+                let jump_to_else = self.emit_placeholder();
+                self.statement(&if_then_branch.then_branch)?;
+                let jump_to_end = self.emit_placeholder();
+
+                self.instructions[jump_to_else] = Instruction::JumpIfFalse(self.instructions.len());
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch)?;
+                }
+                self.instructions[jump_to_end] = Instruction::Jump(self.instructions.len());
+            }
+            Statement::WhileStatement { condition, body, .. } => {
+                let start = self.instructions.len();
+                self.expression(condition)?;
+                let jump_to_end = self.emit_placeholder();
+                self.statement(body)?;
+                self.emit(Instruction::Jump(start));
+                self.instructions[jump_to_end] = Instruction::JumpIfFalse(self.instructions.len());
+            }
+            Statement::ForStatement { .. } => return Err(unsupported("a for loop", statement)),
+            Statement::RepeatStatement { .. } => return Err(unsupported("a repeat loop", statement)),
+            Statement::WhenStatement { .. } => return Err(unsupported("a when statement", statement)),
+            // The body is skipped where it is defined and only entered through calls. The interpreter only calls
+            // functions defined at the top level, so only those are registered.
+            Statement::FunctionDefinition { name, arguments, body, .. } if self.enclosing_spans.len() == 1 => {
+                let jump_over_body = self.emit_placeholder();
+                self.functions.insert(name.value.clone(), FunctionEntry {
+                    address: self.instructions.len(),
+                    parameters: arguments.iter().map(|argument| argument.name.value.clone()).collect(),
+                });
+
+                self.statement(body)?;
+                // Running off the end of the body returns no value
+                self.emit(Instruction::PushUnit);
+                self.emit(Instruction::Return);
+                self.instructions[jump_over_body] = Instruction::Jump(self.instructions.len());
+            }
+            Statement::FunctionDefinition { .. } => return Err(unsupported("a nested function definition", statement)),
+            Statement::ReturnStatement { expression, .. } => {
+                match expression {
+                    Some(expression) => self.expression(expression)?,
+                    None => self.emit(Instruction::PushUnit),
+                }
+                self.emit(Instruction::Return);
+            }
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Result<(), CodegenError> {
+        self.enclosing_spans.push(expression.span());
+        let result = self.expression_instructions(expression);
+        self.enclosing_spans.pop();
+        result
+    }
+
+    fn expression_instructions(&mut self, expression: &Expression) -> Result<(), CodegenError> {
+        match expression {
+            Expression::Literal { value: Literal::Number(value), .. } => {
+                let index = self.constant(*value);
+                self.emit(Instruction::PushInt(index));
+            }
+            Expression::Literal { value: Literal::Boolean(value), .. } => self.emit(Instruction::PushBool(*value)),
+            Expression::Variable(name) => self.emit(Instruction::LoadVar(name.value.clone())),
+            Expression::BinaryOperation { left, operator, right } => {
+                self.expression(left)?;
+                self.expression(right)?;
+                self.emit(binary_instruction(operator));
+            }
+            Expression::UnaryOperation { operator, operand } => {
+                self.expression(operand)?;
+                self.emit(match operator {
+                    UnaryOperator::Negate => Instruction::Negate,
+                    UnaryOperator::Not => Instruction::Not,
+                });
+            }
+            Expression::Grouped(inner) => self.expression(inner)?,
+            Expression::FunctionCall(data) => self.function_call(&data.function_name.value, &data.arguments)?,
+            Expression::List { span, .. } => return Err(CodegenError::Unsupported { construct: "a list", span: span.clone() }),
+            Expression::Index { span, .. } => return Err(CodegenError::Unsupported { construct: "indexing", span: span.clone() }),
+            Expression::Conditional { condition, then_expr, else_expr, .. } => {
+                self.expression(condition)?;
+                let jump_to_else = self.emit_placeholder();
+                self.expression(then_expr)?;
+                let jump_to_end = self.emit_placeholder();
+
+                self.instructions[jump_to_else] = Instruction::JumpIfFalse(self.instructions.len());
+                self.expression(else_expr)?;
+                self.instructions[jump_to_end] = Instruction::Jump(self.instructions.len());
+            }
+            Expression::Error(span) => return Err(CodegenError::Unsupported { construct: "an invalid expression", span: span.clone() }),
+        }
+        Ok(())
+    }
+
+    // Arguments are pushed left to right, so the last one ends up on top of the stack
+    fn function_call(&mut self, name: &str, arguments: &[Expression]) -> Result<(), CodegenError> {
+        for argument in arguments {
+            self.expression(argument)?;
+        }
+        self.emit(Instruction::Call { name: name.to_string(), arg_count: arguments.len() });
+        Ok(())
+    }
+}
+
+impl Default for Codegen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn unsupported(construct: &'static str, statement: &Statement) -> CodegenError {
+    CodegenError::Unsupported { construct, span: statement.span() }
+}
+
+fn binary_instruction(operator: &BinaryOperator) -> Instruction {
+    match operator {
+        BinaryOperator::Add => Instruction::Add,
+        BinaryOperator::Subtract => Instruction::Sub,
+        BinaryOperator::Multiply => Instruction::Mul,
+        BinaryOperator::Divide => Instruction::Div,
+        BinaryOperator::Modulus => Instruction::Mod,
+        BinaryOperator::Power => Instruction::Pow,
+        BinaryOperator::Equal => Instruction::Equal,
+        BinaryOperator::NotEqual => Instruction::NotEqual,
+        BinaryOperator::LessThan => Instruction::LessThan,
+        BinaryOperator::GreaterThan => Instruction::GreaterThan,
+        BinaryOperator::LessThanOrEqual => Instruction::LessThanOrEqual,
+        BinaryOperator::GreaterThanOrEqual => Instruction::GreaterThanOrEqual,
+        BinaryOperator::BitAnd => Instruction::BitAnd,
+        BinaryOperator::BitOr => Instruction::BitOr,
+        BinaryOperator::BitXor => Instruction::BitXor,
+        BinaryOperator::ShiftLeft => Instruction::ShiftLeft,
+        BinaryOperator::ShiftRight => Instruction::ShiftRight,
+        BinaryOperator::And => Instruction::And,
+        BinaryOperator::Or => Instruction::Or,
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{ast::{expression::{BinaryOperator, Expression, UnaryOperator}, statement::{Parameter, Statement}, Ast, AstExplorer}, lexer::{TextSpan, Token}, types::Type};
+
+// For each defined function, the functions its body calls.
+// Calls made outside of any function are not recorded.
+pub struct CallGraph {
+    callees: BTreeMap<String, BTreeSet<String>>,
+    function_stack: Vec<String>,
+}
+
+impl CallGraph {
+    pub fn build(ast: &Ast) -> Self {
+        let mut call_graph = CallGraph { callees: BTreeMap::new(), function_stack: Vec::new() };
+        call_graph.explore_ast(ast);
+        call_graph
+    }
+
+    pub fn callees_of(&self, name: &str) -> Vec<&str> {
+        self.callees
+            .get(name)
+            .map(|callees| callees.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn callers_of(&self, name: &str) -> Vec<&str> {
+        self.callees
+            .iter()
+            .filter(|(_, callees)| callees.contains(name))
+            .map(|(caller, _)| caller.as_str())
+            .collect()
+    }
+
+    // Functions that can reach themselves through their calls, directly or through other functions
+    pub fn detect_recursion(&self) -> Vec<&str> {
+        self.callees
+            .keys()
+            .filter(|function| self.reaches(function, function))
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn reaches(&self, from: &str, target: &str) -> bool {
+        let mut visited = BTreeSet::new();
+        let mut pending = self.callees_of(from);
+
+        while let Some(function) = pending.pop() {
+            if function == target {
+                return true;
+            }
+            if visited.insert(function) {
+                pending.extend(self.callees_of(function));
+            }
+        }
+        false
+    }
+}
+
+impl AstExplorer for CallGraph {
+    fn visit_variable_declaration(&mut self, _name: &Token, _type_annotation: Option<&Type>, value: &Expression) {
+        self.visit_expression(value);
+    }
+
+    fn visit_const_declaration(&mut self, _name: &Token, value: &Expression) {
+        self.visit_expression(value);
+    }
+
+    fn visit_variable_assignement(&mut self, _name: &Token, value: &Expression) {
+        self.visit_expression(value);
+    }
+
+    fn visit_swap_statement(&mut self, _left: &Token, _right: &Token) {
+    }
+
+    fn visit_if_statement(&mut self, condition: &Expression, then_branch: &Statement, else_branch: Option<&Statement>) {
+        self.visit_expression(condition);
+        self.visit_statement(then_branch);
+        if let Some(else_branch) = else_branch {
+            self.visit_statement(else_branch);
+        }
+    }
+
+    fn visit_while_statement(&mut self, condition: &Expression, body: &Statement) {
+        self.visit_expression(condition);
+        self.visit_statement(body);
+    }
+
+    fn visit_for_statement(&mut self, _variable: &Token, _retains_variable: bool, start: &Expression, end: &Expression, step: &Option<Expression>, body: &Statement) {
+        self.visit_expression(start);
+        self.visit_expression(end);
+        if let Some(step) = step {
+            self.visit_expression(step);
+        }
+        self.visit_statement(body);
+    }
+
+    fn visit_repeat_statement(&mut self, count: &Expression, body: &Statement) {
+        self.visit_expression(count);
+        self.visit_statement(body);
+    }
+
+    fn visit_when_statement(&mut self, subject: &Expression, arms: &[(Expression, Statement)], default: Option<&Statement>) {
+        self.visit_expression(subject);
+        for (value, body) in arms {
+            self.visit_expression(value);
+            self.visit_statement(body);
+        }
+        if let Some(default) = default {
+            self.visit_statement(default);
+        }
+    }
+
+    fn visit_function_definition(&mut self, name: &Token, _arguments: &[Parameter], _return_type: Option<&Type>, body: &Statement) {
+        self.callees.entry(name.value.clone()).or_default();
+        self.function_stack.push(name.value.clone());
+        self.visit_statement(body);
+        self.function_stack.pop();
+    }
+
+    fn visit_function_call(&mut self, function_name: &Token, arguments: &[Expression]) {
+        if let Some(caller) = self.function_stack.last() {
+            self.callees.entry(caller.clone()).or_default().insert(function_name.value.clone());
+        }
+        for argument in arguments {
+            self.visit_expression(argument);
+        }
+    }
+
+    fn visit_return_statement(&mut self, _span: TextSpan, expression: &Option<Expression>) {
+        if let Some(expression) = expression {
+            self.visit_expression(expression);
+        }
+    }
+
+    fn block_statement_on_enter(&mut self) {
+    }
+
+    fn block_statement_on_exit(&mut self) {
+    }
+
+    fn visit_number_expression(&mut self, _value: i64) {
+    }
+
+    fn visit_boolean_expression(&mut self, _value: bool) {
+    }
+
+    fn visit_variable_expression(&mut self, _name: &Token) {
+    }
+
+    fn visit_binary_operation(&mut self, left: &Expression, _operator: &BinaryOperator, right: &Expression) {
+        self.visit_expression(left);
+        self.visit_expression(right);
+    }
+
+    fn visit_unary_operation(&mut self, _operator: &UnaryOperator, operand: &Expression) {
+        self.visit_expression(operand);
+    }
+
+    fn visit_list_expression(&mut self, elements: &[Expression]) {
+        for element in elements {
+            self.visit_expression(element);
+        }
+    }
+
+    fn visit_index_expression(&mut self, target: &Expression, index: &Expression) {
+        self.visit_expression(target);
+        self.visit_expression(index);
+    }
+
+    fn visit_conditional_expression(&mut self, condition: &Expression, then_expr: &Expression, else_expr: &Expression) {
+        self.visit_expression(condition);
+        self.visit_expression(then_expr);
+        self.visit_expression(else_expr);
+    }
+
+    fn visit_error_expression(&mut self) {
+    }
+}
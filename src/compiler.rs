@@ -1,37 +1,196 @@
-use std::{fs, io, path::Path};
+use std::{cell::OnceCell, fs, io::{self, Read}, path::Path};
 
-use crate::{ast::Ast, diagnostic::Diagnostics, lexer::Lexer, parser::Parser, resolver::Resolver, symbols_table::SymbolsTable};
+use crate::{ast::Ast, diagnostic::Diagnostics, lexer::{Lexer, Token, TokenPosition}, parser::Parser, resolver::{Resolver, ResolverOutput}, symbols_table::{ScopeId, SymbolsTable}, types::TypeMap};
 
 pub struct CompilationUnit {
     pub ast: Ast,
     pub symbols_table: SymbolsTable,
+    pub type_map: TypeMap,
+    pub warnings: Diagnostics,
+}
+
+impl CompilationUnit {
+    // The innermost scope around a source position, e.g. to list the variables visible there.
+    // After `append` the positions of the latest source take precedence.
+    pub fn scope_at(&self, position: &TokenPosition) -> ScopeId {
+        self.symbols_table.scope_at(position)
+    }
+
+    // Compiles `source_code` as a continuation of this unit, seeing its functions and global variables.
+    // The new statements are only added when they compile without errors.
+    pub fn append(&mut self, source_code: &SourceCode) -> Diagnostics {
+        let parser = Parser::new(Lexer::new(source_code.as_str()));
+        let (ast, mut diagnostics) = parser.parse_recovering();
+
+        match Resolver::with_symbols_table(self.symbols_table.clone()).resolve(&ast) {
+            Ok(ResolverOutput { symbols_table, type_map, warnings }) => {
+                diagnostics.merge(warnings);
+                diagnostics.sort_by_position();
+                if diagnostics.has_errors() {
+                    return diagnostics;
+                }
+
+                self.symbols_table = symbols_table;
+                self.type_map.merge(type_map);
+                for statement in ast.statements() {
+                    self.ast.add_statement(statement.clone());
+                }
+                diagnostics
+            }
+            Err(resolver_diagnostics) => {
+                diagnostics.merge(resolver_diagnostics);
+                diagnostics.sort_by_position();
+                diagnostics
+            }
+        }
+    }
 }
 
 pub struct SourceCode {
     code: String,
+    line_starts: OnceCell<Vec<usize>>,
 }
 
 impl SourceCode {
     pub fn from_string(code: String) -> Self {
-        SourceCode { code }
+        SourceCode { code, line_starts: OnceCell::new() }
     }
 
     pub fn as_str(&self) -> &str {
         &self.code
     }
 
+    // Lines are numbered from 1, like token positions
+    pub fn line(&self, n: usize) -> Option<&str> {
+        let line_starts = self.line_starts.get_or_init(|| {
+            std::iter::once(0)
+                .chain(self.code.match_indices('\n').map(|(index, _)| index + 1))
+                .filter(|&start| start < self.code.len())
+                .collect()
+        });
+
+        let start = *line_starts.get(n.checked_sub(1)?)?;
+        let end = line_starts.get(n).copied().unwrap_or(self.code.len());
+        let line = &self.code[start..end];
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        Some(line.strip_suffix('\r').unwrap_or(line))
+    }
+
     pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
         Ok(Self::from_string(fs::read_to_string(path)?))
     }
+
+    // Reads the whole program, e.g. from stdin
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let mut code = String::new();
+        reader.read_to_string(&mut code)?;
+        Ok(Self::from_string(code))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntWidth {
+    Bits8,
+    Bits16,
+    Bits32,
+    Bits64,
+}
+
+impl IntWidth {
+    pub fn bits(&self) -> u32 {
+        match self {
+            IntWidth::Bits8 => 8,
+            IntWidth::Bits16 => 16,
+            IntWidth::Bits32 => 32,
+            IntWidth::Bits64 => 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowMode {
+    Wrap,
+    Error,
+}
+
+// How `/` and `%` round when the operands have different signs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DivisionMode {
+    // Rounds toward zero, like C and Rust: -7 / 2 is -3 and -7 % 2 is -1
+    Truncate,
+    // Rounds toward negative infinity, like Python: -7 / 2 is -4 and -7 % 2 is 1
+    Floor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompilerOptions {
+    pub int_width: IntWidth,
+    pub overflow: OverflowMode,
+    pub division: DivisionMode,
+}
+
+impl CompilerOptions {
+    // Whether a value lies in the configured signed integer range
+    pub fn fits_integer(&self, value: i128) -> bool {
+        let bits = self.int_width.bits();
+        let min = -(1i128 << (bits - 1));
+        let max = (1i128 << (bits - 1)) - 1;
+
+        (min..=max).contains(&value)
+    }
+
+    // Brings an arithmetic result into the configured signed integer range, None if it overflows in error mode
+    pub fn fit_integer(&self, value: i128) -> Option<i64> {
+        if self.fits_integer(value) {
+            return Some(value as i64);
+        }
+
+        match self.overflow {
+            OverflowMode::Wrap => {
+                let shift = 128 - self.int_width.bits();
+                Some(((value << shift) >> shift) as i64)
+            }
+            OverflowMode::Error => None,
+        }
+    }
+}
+
+impl Default for CompilerOptions {
+    fn default() -> Self {
+        CompilerOptions {
+            int_width: IntWidth::Bits64,
+            overflow: OverflowMode::Error,
+            division: DivisionMode::Truncate,
+        }
+    }
 }
 
 pub struct Compiler {
-    _private: (),
+    options: CompilerOptions,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Compiler {
     pub fn new() -> Self {
-        Compiler { _private: () }
+        Self::with_options(CompilerOptions::default())
+    }
+
+    pub fn with_options(options: CompilerOptions) -> Self {
+        Compiler { options }
+    }
+
+    pub fn options(&self) -> &CompilerOptions {
+        &self.options
+    }
+
+    // The tokens `compile` would parse, ending with the EndOfFile token, e.g. for syntax highlighting
+    pub fn lex(&self, source_code: &SourceCode) -> Vec<Token> {
+        Lexer::new(source_code.as_str()).collect()
     }
 
     pub fn compile(&self, source_code: &SourceCode) -> Result<CompilationUnit, Diagnostics> {
@@ -40,14 +199,30 @@ impl Compiler {
         let parser = Parser::new(lexer);
         
         println!("Parsing tokens...");
-        let ast = parser.parse()?;
+        let (ast, mut diagnostics) = parser.parse_recovering();
 
+        // Resolving the recovered AST reports semantic errors alongside the syntax ones
         println!("Resolving symbols...");
-        let symbols_table = Resolver::new().resolve(&ast)?;
+        match Resolver::new().resolve(&ast) {
+            Ok(ResolverOutput { symbols_table, type_map, warnings }) => {
+                diagnostics.merge(warnings);
+                diagnostics.sort_by_position();
+                if diagnostics.has_errors() {
+                    return Err(diagnostics);
+                }
 
-        Ok(CompilationUnit {
-            ast,
-            symbols_table,
-        })
+                Ok(CompilationUnit {
+                    ast,
+                    symbols_table,
+                    type_map,
+                    warnings: diagnostics,
+                })
+            },
+            Err(resolver_diagnostics) => {
+                diagnostics.merge(resolver_diagnostics);
+                diagnostics.sort_by_position();
+                Err(diagnostics)
+            }
+        }
     }
 }
\ No newline at end of file
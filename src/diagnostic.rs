@@ -10,18 +10,66 @@ enum DiagnosticError {
         expected: Vec<TokenKind>,
         found: String,
     },
-    UnexpectedElseAfterEnd,
+    MisspelledOperator {
+        found: String,
+        suggestion: String,
+    },
+    UnknownCharacters {
+        found: String,
+    },
+    UnexpectedEndOfInput {
+        expected: Vec<TokenKind>,
+    },
+    InvalidDigitSeparator {
+        found: String,
+    },
+    IntegerLiteralOutOfRange {
+        found: String,
+    },
+    UnitValueUsed {
+        function_name: String,
+    },
+    MainWithParameters,
+    MissingReturn {
+        function_name: String,
+    },
+    SkippedByMain,
+    EqualInsteadOfKeyword {
+        keyword: TokenKind,
+    },
+    MisspelledKeyword {
+        found: String,
+        suggestion: TokenKind,
+    },
+    UnexpectedElseAfterEnd {
+        keyword: TokenKind,
+    },
     UnexpectedEndToken,
-    UnexpectedElseToken,
+    UnexpectedElseToken {
+        keyword: TokenKind,
+    },
+    AssignmentInExpression,
 
     VariableRedefinition {
         identifier: String,
     },
 
+    VariableShadowing {
+        identifier: String,
+    },
+
     UndefinedVariable {
         identifier: String,
     },
 
+    AssignmentToConst {
+        identifier: String,
+    },
+
+    UnusedVariable {
+        identifier: String,
+    },
+
     FunctionArgumentsMismatch {
         function_name: String,
         expected: usize,
@@ -32,8 +80,33 @@ enum DiagnosticError {
         function_name: String,
     },
 
+    FunctionRedefinition {
+        function_name: String,
+    },
+
+    DuplicateParameter {
+        name: String,
+    },
+
     ReturnOutsideFunction,
 
+    EmptyFunctionBody {
+        function_name: String,
+    },
+
+    UnreachableCode,
+
+    UnreachableBranch {
+        condition_value: bool,
+    },
+
+    ZeroStep,
+
+    SelfComparison {
+        operator: BinaryOperator,
+        result: bool,
+    },
+
     VariableTypeMismatch {
         identifier: String,
         expected_type: Type,
@@ -55,6 +128,56 @@ enum DiagnosticError {
         operand_type: Type,
         operator: UnaryOperator,
     },
+
+    NotIndexable {
+        found_type: Type,
+    },
+}
+
+impl DiagnosticError {
+    // Codes are stable: a code is never reused, so it can be searched for in logs and documentation.
+    // E0001-E0009 are syntax errors, E0010-E0019 name errors, E0020-E0029 type errors and W codes warnings.
+    // Errors added after that are numbered from E0030.
+    fn code(&self) -> &'static str {
+        match self {
+            DiagnosticError::UnexpectedToken { .. } => "E0001",
+            DiagnosticError::UnexpectedEndOfInput { .. } => "E0002",
+            DiagnosticError::UnknownCharacters { .. } => "E0003",
+            DiagnosticError::MisspelledOperator { .. } => "E0004",
+            DiagnosticError::MisspelledKeyword { .. } => "E0005",
+            DiagnosticError::UnexpectedElseAfterEnd { .. } => "E0006",
+            DiagnosticError::UnexpectedEndToken => "E0007",
+            DiagnosticError::UnexpectedElseToken { .. } => "E0008",
+            DiagnosticError::AssignmentInExpression => "E0009",
+            DiagnosticError::UndefinedVariable { .. } => "E0010",
+            DiagnosticError::VariableRedefinition { .. } => "E0011",
+            DiagnosticError::AssignmentToConst { .. } => "E0012",
+            DiagnosticError::UndefinedFunction { .. } => "E0013",
+            DiagnosticError::FunctionRedefinition { .. } => "E0014",
+            DiagnosticError::FunctionArgumentsMismatch { .. } => "E0015",
+            DiagnosticError::DuplicateParameter { .. } => "E0016",
+            DiagnosticError::ReturnOutsideFunction => "E0017",
+            DiagnosticError::VariableTypeMismatch { .. } => "E0020",
+            DiagnosticError::ExpressionTypeMismatch { .. } => "E0021",
+            DiagnosticError::IncompatibleBinaryOperation { .. } => "E0022",
+            DiagnosticError::IncompatibleUnaryOperation { .. } => "E0023",
+            DiagnosticError::NotIndexable { .. } => "E0024",
+            DiagnosticError::InvalidDigitSeparator { .. } => "E0030",
+            DiagnosticError::UnitValueUsed { .. } => "E0031",
+            DiagnosticError::MainWithParameters => "E0032",
+            DiagnosticError::IntegerLiteralOutOfRange { .. } => "E0033",
+            DiagnosticError::MissingReturn { .. } => "E0034",
+            DiagnosticError::SkippedByMain => "W0009",
+            DiagnosticError::EqualInsteadOfKeyword { .. } => "W0001",
+            DiagnosticError::VariableShadowing { .. } => "W0002",
+            DiagnosticError::UnusedVariable { .. } => "W0003",
+            DiagnosticError::EmptyFunctionBody { .. } => "W0004",
+            DiagnosticError::UnreachableCode => "W0005",
+            DiagnosticError::UnreachableBranch { .. } => "W0006",
+            DiagnosticError::ZeroStep => "W0007",
+            DiagnosticError::SelfComparison { .. } => "W0008",
+        }
+    }
 }
 
 impl fmt::Display for DiagnosticError {
@@ -68,18 +191,41 @@ impl fmt::Display for DiagnosticError {
                                                                                     .join(", ");
                                                                                 write!(f, "Unexpected token '{}'. expected one of [{}]", found, expected_str)
                                                                             }
-            DiagnosticError::UnexpectedElseAfterEnd => {
-                                                                                write!(f, "Unexpected 'else' after 'end'")
+            DiagnosticError::MisspelledOperator { found, suggestion } => {
+                                                                                write!(f, "Unknown operator '{}'. Did you mean '{}'?", found, suggestion)
+                                                                            }
+            DiagnosticError::AssignmentInExpression => write!(f, "assignment is not an expression in NavaCode"),
+            DiagnosticError::UnknownCharacters { found } => write!(f, "Unknown characters '{}'", found),
+            DiagnosticError::InvalidDigitSeparator { found } => write!(f, "Misplaced '_' in number '{}'. '_' can only separate two digits", found),
+            DiagnosticError::IntegerLiteralOutOfRange { found } => write!(f, "Integer literal '{}' is out of range, it must be between {} and {}", found, i64::MIN, i64::MAX),
+            DiagnosticError::UnexpectedEndOfInput { expected } => {
+                let expected_str = expected.iter().map(|k| format!("{}", k)).collect::<Vec<_>>().join(", ");
+                write!(f, "Unexpected end of file. expected one of [{}]", expected_str)
+            }
+            DiagnosticError::EqualInsteadOfKeyword { keyword } => write!(f, "Use '{}' instead of '='", keyword),
+            DiagnosticError::MisspelledKeyword { found, suggestion } => write!(f, "Unknown statement '{}'. Did you mean '{}'?", found, suggestion),
+            DiagnosticError::UnexpectedElseAfterEnd { keyword } => {
+                                                                                write!(f, "Unexpected '{}' after 'end'", keyword)
                                                                             }
             DiagnosticError::UnexpectedEndToken => {
                                                                                 write!(f, "'end' present without a matching block")
                                                                             }
-            DiagnosticError::UnexpectedElseToken => write!(f, "'else' present without a matching 'if'"),
+            DiagnosticError::UnexpectedElseToken { keyword } => write!(f, "'{}' present without a matching 'if'", keyword),
             DiagnosticError::VariableRedefinition { identifier } => write!(f, "Variable '{}' is already defined in the current scope", identifier),
+            DiagnosticError::VariableShadowing { identifier } => write!(f, "Variable '{}' shadows a variable defined in an outer scope", identifier),
             DiagnosticError::UndefinedVariable { identifier } => write!(f, "Variable '{}' is not defined", identifier),
+            DiagnosticError::AssignmentToConst { identifier } => write!(f, "Cannot assign to constant '{}'", identifier),
+            DiagnosticError::UnusedVariable { identifier } => write!(f, "Variable '{}' is declared but never used", identifier),
             DiagnosticError::FunctionArgumentsMismatch { function_name, expected, found } => write!(f, "Function '{}' called with incorrect number of arguments: expected {}, found {}", function_name, expected, found),
             DiagnosticError::UndefinedFunction { function_name } => write!(f, "Function '{}' is not defined", function_name),
+            DiagnosticError::FunctionRedefinition { function_name } => write!(f, "Function '{}' is already defined", function_name),
+            DiagnosticError::DuplicateParameter { name } => write!(f, "Parameter '{}' is already declared in this function", name),
             DiagnosticError::ReturnOutsideFunction => write!(f, "Return statement outside of function"),
+            DiagnosticError::EmptyFunctionBody { function_name } => write!(f, "Function '{}' has an empty body", function_name),
+            DiagnosticError::UnreachableCode => write!(f, "Unreachable code after return statement"),
+            DiagnosticError::UnreachableBranch { condition_value } => write!(f, "Unreachable branch: the condition is always {}", condition_value),
+            DiagnosticError::ZeroStep => write!(f, "A for loop with a step of 0 never ends"),
+            DiagnosticError::SelfComparison { operator, result } => write!(f, "Comparing an expression to itself with '{}' is always {}", operator, result),
             DiagnosticError::VariableTypeMismatch { identifier, expected_type, found_type } => {
                                         write!(f, "Type mismatch for variable '{}': expected '{}', found '{}'", identifier, expected_type, found_type)
                                     },
@@ -92,6 +238,11 @@ impl fmt::Display for DiagnosticError {
             DiagnosticError::IncompatibleUnaryOperation { operand_type, operator } => {
                         write!(f, "Incompatible unary operation: '{}' (operand: '{}')", operator, operand_type)
                     },
+            DiagnosticError::UnitValueUsed { function_name } => write!(f, "Function '{}' does not return a value, so its call cannot be used as one", function_name),
+            DiagnosticError::MainWithParameters => write!(f, "Function 'main' is the entry point of the program and cannot take parameters"),
+            DiagnosticError::MissingReturn { function_name } => write!(f, "Not all paths of function '{}' return a value", function_name),
+            DiagnosticError::SkippedByMain => write!(f, "Top-level statement is never run because the program defines 'main'"),
+            DiagnosticError::NotIndexable { found_type } => write!(f, "Values of type '{}' cannot be indexed", found_type),
         }
     }
 }
@@ -99,30 +250,129 @@ impl fmt::Display for DiagnosticError {
 #[derive(Debug)]
 enum DiagnosticType {
     Error(DiagnosticError),
+    Warning(DiagnosticError),
+}
+
+// Ordered by when each phase runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CompilePhase {
+    Lex,
+    Parse,
+    Resolve,
 }
 
 #[derive(Debug)]
 pub struct Diagnostic {
     diagnostic_type: DiagnosticType,
     span: TextSpan,
+    phase: CompilePhase,
 }
 
 impl Diagnostic {
+    pub fn phase(&self) -> CompilePhase {
+        self.phase
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self.diagnostic_type, DiagnosticType::Error(_))
+    }
+
+    pub fn code(&self) -> &'static str {
+        match &self.diagnostic_type {
+            DiagnosticType::Error(err) | DiagnosticType::Warning(err) => err.code(),
+        }
+    }
+
+    // The program stopped midway, e.g. `let x be 1 +`, so more input could complete it
+    pub fn is_unexpected_end_of_input(&self) -> bool {
+        matches!(self.diagnostic_type, DiagnosticType::Error(DiagnosticError::UnexpectedEndOfInput { .. }))
+    }
+
     pub fn unexpected_token(expected: Vec<TokenKind>, found: Token) -> Self {
         let span = found.span();
+        if found.kind == TokenKind::EndOfFile {
+            return Self {
+                diagnostic_type: DiagnosticType::Error(DiagnosticError::UnexpectedEndOfInput { expected }),
+                span,
+                phase: CompilePhase::Parse,
+            };
+        }
+        // Characters the lexer could not recognize surface as Unknown tokens
+        let phase = if found.kind == TokenKind::Unknown { CompilePhase::Lex } else { CompilePhase::Parse };
         Self {
             diagnostic_type: DiagnosticType::Error(DiagnosticError::UnexpectedToken {
                 expected,
                 found: found.value,
             }),
-            span
+            span,
+            phase,
+        }
+    }
+
+    pub fn unknown_characters(found: Token) -> Self {
+        let span = found.span();
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::UnknownCharacters { found: found.value }),
+            span,
+            phase: CompilePhase::Lex,
+        }
+    }
+
+    pub fn invalid_digit_separator(number: Token) -> Self {
+        let span = number.span();
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::InvalidDigitSeparator { found: number.value }),
+            span,
+            phase: CompilePhase::Lex,
+        }
+    }
+
+    pub fn integer_literal_out_of_range(found: String, span: TextSpan) -> Self {
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::IntegerLiteralOutOfRange { found }),
+            span,
+            phase: CompilePhase::Lex,
+        }
+    }
+
+    pub fn misspelled_operator(found: String, suggestion: String, span: TextSpan) -> Self {
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::MisspelledOperator { found, suggestion }),
+            span,
+            phase: CompilePhase::Parse,
+        }
+    }
+
+    pub fn assignment_in_expression(span: TextSpan) -> Self {
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::AssignmentInExpression),
+            span,
+            phase: CompilePhase::Parse,
+        }
+    }
+
+    pub fn misspelled_keyword(found: Token, suggestion: TokenKind) -> Self {
+        let span = found.span();
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::MisspelledKeyword { found: found.value, suggestion }),
+            span,
+            phase: CompilePhase::Parse,
+        }
+    }
+
+    pub fn equal_instead_of_keyword(keyword: TokenKind, span: TextSpan) -> Self {
+        Self {
+            diagnostic_type: DiagnosticType::Warning(DiagnosticError::EqualInsteadOfKeyword { keyword }),
+            span,
+            phase: CompilePhase::Parse,
         }
     }
 
-    pub fn unexpected_else_after_end(span: TextSpan) -> Self {
+    pub fn unexpected_else_after_end(keyword: TokenKind, span: TextSpan) -> Self {
         Self {
-            diagnostic_type: DiagnosticType::Error(DiagnosticError::UnexpectedElseAfterEnd),
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::UnexpectedElseAfterEnd { keyword }),
             span,
+            phase: CompilePhase::Parse,
         }
     } 
 
@@ -130,13 +380,15 @@ impl Diagnostic {
         Self {
             diagnostic_type: DiagnosticType::Error(DiagnosticError::UnexpectedEndToken),
             span,
+            phase: CompilePhase::Parse,
         }
     }
 
-    pub fn unexpected_else_token(span: TextSpan) -> Self {
+    pub fn unexpected_else_token(keyword: TokenKind, span: TextSpan) -> Self {
         Self {
-            diagnostic_type: DiagnosticType::Error(DiagnosticError::UnexpectedElseToken),
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::UnexpectedElseToken { keyword }),
             span,
+            phase: CompilePhase::Parse,
         }
     }
 
@@ -145,6 +397,16 @@ impl Diagnostic {
         Self {
             diagnostic_type: DiagnosticType::Error(DiagnosticError::VariableRedefinition { identifier: variable.value }),
             span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn variable_shadowing(variable: Token) -> Self {
+        let span = variable.span();
+        Self {
+            diagnostic_type: DiagnosticType::Warning(DiagnosticError::VariableShadowing { identifier: variable.value }),
+            span,
+            phase: CompilePhase::Resolve,
         }
     }
 
@@ -153,6 +415,24 @@ impl Diagnostic {
         Self {
             diagnostic_type: DiagnosticType::Error(DiagnosticError::UndefinedVariable { identifier: variable.value }),
             span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn assignment_to_const(variable: Token) -> Self {
+        let span = variable.span();
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::AssignmentToConst { identifier: variable.value }),
+            span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn unused_variable(identifier: String, span: TextSpan) -> Self {
+        Self {
+            diagnostic_type: DiagnosticType::Warning(DiagnosticError::UnusedVariable { identifier }),
+            span,
+            phase: CompilePhase::Resolve,
         }
     }
 
@@ -165,6 +445,18 @@ impl Diagnostic {
                 found,
             }),
             span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn function_redefinition(function_name: Token) -> Self {
+        let span = function_name.span();
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::FunctionRedefinition {
+                function_name: function_name.value,
+            }),
+            span,
+            phase: CompilePhase::Resolve,
         }
     }
 
@@ -175,6 +467,16 @@ impl Diagnostic {
                 function_name: function_name.value,
             }),
             span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn duplicate_parameter(parameter: Token) -> Self {
+        let span = parameter.span();
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::DuplicateParameter { name: parameter.value }),
+            span,
+            phase: CompilePhase::Resolve,
         }
     }
 
@@ -182,6 +484,48 @@ impl Diagnostic {
         Self {
             diagnostic_type: DiagnosticType::Error(DiagnosticError::ReturnOutsideFunction),
             span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn empty_function_body(function_name: Token) -> Self {
+        let span = function_name.span();
+        Self {
+            diagnostic_type: DiagnosticType::Warning(DiagnosticError::EmptyFunctionBody { function_name: function_name.value }),
+            span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn unreachable_code(span: TextSpan) -> Self {
+        Self {
+            diagnostic_type: DiagnosticType::Warning(DiagnosticError::UnreachableCode),
+            span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn unreachable_branch(condition_value: bool, span: TextSpan) -> Self {
+        Self {
+            diagnostic_type: DiagnosticType::Warning(DiagnosticError::UnreachableBranch { condition_value }),
+            span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn zero_step(span: TextSpan) -> Self {
+        Self {
+            diagnostic_type: DiagnosticType::Warning(DiagnosticError::ZeroStep),
+            span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn self_comparison(operator: BinaryOperator, result: bool, span: TextSpan) -> Self {
+        Self {
+            diagnostic_type: DiagnosticType::Warning(DiagnosticError::SelfComparison { operator, result }),
+            span,
+            phase: CompilePhase::Resolve,
         }
     }
 
@@ -194,6 +538,7 @@ impl Diagnostic {
                 found_type,
             }),
             span,
+            phase: CompilePhase::Resolve,
         }
     }
 
@@ -204,6 +549,7 @@ impl Diagnostic {
                 found_type,
             }),
             span,
+            phase: CompilePhase::Resolve,
         }
     }
 
@@ -215,6 +561,7 @@ impl Diagnostic {
                 operator,
             }),
             span,
+            phase: CompilePhase::Resolve,
         }
     }
 
@@ -225,6 +572,50 @@ impl Diagnostic {
                 operator,
             }),
             span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn unit_value_used(function_name: Token) -> Self {
+        let span = function_name.span();
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::UnitValueUsed { function_name: function_name.value }),
+            span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn main_with_parameters(function_name: Token) -> Self {
+        let span = function_name.span();
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::MainWithParameters),
+            span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn missing_return(function_name: Token) -> Self {
+        let span = function_name.span();
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::MissingReturn { function_name: function_name.value }),
+            span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn skipped_by_main(span: TextSpan) -> Self {
+        Self {
+            diagnostic_type: DiagnosticType::Warning(DiagnosticError::SkippedByMain),
+            span,
+            phase: CompilePhase::Resolve,
+        }
+    }
+
+    pub fn not_indexable(found_type: Type, span: TextSpan) -> Self {
+        Self {
+            diagnostic_type: DiagnosticType::Error(DiagnosticError::NotIndexable { found_type }),
+            span,
+            phase: CompilePhase::Resolve,
         }
     }
 }
@@ -233,7 +624,10 @@ impl fmt::Display for Diagnostic {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.diagnostic_type {
             DiagnosticType::Error(err) => {
-                write!(f, "ERROR: at {}:{}: {}", self.span.start.line, self.span.start.column, err)
+                write!(f, "ERROR[{}]: at {}:{}: {}", err.code(), self.span.start.line, self.span.start.column, err)
+            }
+            DiagnosticType::Warning(warn) => {
+                write!(f, "WARNING[{}]: at {}:{}: {}", warn.code(), self.span.start.line, self.span.start.column, warn)
             }
         }
     }
@@ -253,8 +647,32 @@ impl Diagnostics {
         self.diagnostics.push(diag);
     }
 
+    pub fn merge(&mut self, other: Diagnostics) {
+        self.diagnostics.extend(other.diagnostics);
+    }
+
+    // Orders by where each diagnostic starts in the source, keeping the report order at the same position
+    pub fn sort_by_position(&mut self) {
+        self.diagnostics.sort_by_key(|diagnostic| diagnostic.span.start.clone());
+    }
+
     pub fn has_errors(&self) -> bool {
-        self.diagnostics.iter().any(|d| matches!(d.diagnostic_type, DiagnosticType::Error(_)))
+        self.diagnostics.iter().any(|d| d.is_error())
+    }
+
+    // The earliest phase that reported an error, None if there are only warnings
+    pub fn phase(&self) -> Option<CompilePhase> {
+        self.diagnostics.iter().filter(|d| d.is_error()).map(|d| d.phase).min()
+    }
+
+    pub fn has_warnings(&self) -> bool {
+        self.diagnostics.iter().any(|d| matches!(d.diagnostic_type, DiagnosticType::Warning(_)))
+    }
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
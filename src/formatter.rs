@@ -0,0 +1,330 @@
+use crate::{ast::{expression::{BinaryOperator, Expression, Literal, UnaryOperator}, statement::{Parameter, Statement}, Ast, AstExplorer}, lexer::{TextSpan, Token}, types::Type};
+
+const INDENT: &str = "    ";
+
+// Re-emits an AST as NavaCode source with normalized indentation and spacing
+pub struct SourceFormatter {
+    output: String,
+    indent_level: usize,
+}
+
+impl SourceFormatter {
+    pub fn new() -> Self {
+        SourceFormatter { output: String::new(), indent_level: 0 }
+    }
+
+    pub fn format(ast: &Ast) -> String {
+        let mut formatter = Self::new();
+        formatter.explore_ast(ast);
+        formatter.output
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn write_indent(&mut self) {
+        self.output.push_str(&INDENT.repeat(self.indent_level));
+    }
+
+    fn write_operand(&mut self, operand: &Expression, needs_parens: bool) {
+        if needs_parens {
+            self.write("(");
+            self.visit_expression(operand);
+            self.write(")");
+        } else {
+            self.visit_expression(operand);
+        }
+    }
+
+    fn write_expressions_list(&mut self, expressions: &[Expression]) {
+        for (index, expression) in expressions.iter().enumerate() {
+            if index > 0 {
+                self.write(", ");
+            }
+            self.visit_expression(expression);
+        }
+    }
+
+    // Writes the `if ... then` header and branches, sharing the `end` of an `else if` chain
+    fn write_if_statement(&mut self, condition: &Expression, then_branch: &Statement, else_branch: Option<&Statement>) {
+        self.write("if ");
+        self.visit_expression(condition);
+        self.write(" then\n");
+        self.visit_statement(then_branch);
+
+        match else_branch {
+            Some(Statement::IfStatement { if_then_branch, else_branch, .. }) => {
+                self.write_indent();
+                self.write("else ");
+                self.write_if_statement(&if_then_branch.condition, &if_then_branch.then_branch, else_branch.as_deref());
+            }
+            Some(else_branch) => {
+                self.write_indent();
+                self.write("else\n");
+                self.visit_statement(else_branch);
+                self.write_indent();
+                self.write("end");
+            }
+            None => {
+                self.write_indent();
+                self.write("end");
+            }
+        }
+    }
+
+    fn write_block_end(&mut self, body: &Statement) {
+        self.visit_statement(body);
+        self.write_indent();
+        self.write("end");
+    }
+}
+
+impl Default for SourceFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AstExplorer for SourceFormatter {
+    fn visit_statement(&mut self, statement: &Statement) {
+        if let Statement::BlockStatement { .. } = statement {
+            self.visit_statement_impl(statement);
+            return;
+        }
+
+        self.write_indent();
+        match statement {
+            // Only a literal or '(' can start an expression statement, so anything else keeps its parentheses
+            Statement::ExpressionStatement(expression) => self.write_operand(expression, !starts_with_literal(expression)),
+            _ => self.visit_statement_impl(statement),
+        }
+        self.write("\n");
+    }
+
+    fn visit_variable_declaration(&mut self, name: &Token, type_annotation: Option<&Type>, value: &Expression) {
+        match type_annotation {
+            Some(annotation) => self.write(&format!("let {}: {} be ", name.value, annotation)),
+            None => self.write(&format!("let {} be ", name.value)),
+        }
+        self.visit_expression(value);
+    }
+
+    fn visit_const_declaration(&mut self, name: &Token, value: &Expression) {
+        self.write(&format!("const {} be ", name.value));
+        self.visit_expression(value);
+    }
+
+    fn visit_variable_assignement(&mut self, name: &Token, value: &Expression) {
+        self.write(&format!("set {} to ", name.value));
+        self.visit_expression(value);
+    }
+
+    fn visit_swap_statement(&mut self, left: &Token, right: &Token) {
+        self.write(&format!("swap {} and {}", left.value, right.value));
+    }
+
+    fn visit_if_statement(&mut self, condition: &Expression, then_branch: &Statement, else_branch: Option<&Statement>) {
+        self.write_if_statement(condition, then_branch, else_branch);
+    }
+
+    fn visit_while_statement(&mut self, condition: &Expression, body: &Statement) {
+        self.write("while ");
+        self.visit_expression(condition);
+        self.write(" do\n");
+        self.write_block_end(body);
+    }
+
+    fn visit_for_statement(&mut self, variable: &Token, retains_variable: bool, start: &Expression, end: &Expression, step: &Option<Expression>, body: &Statement) {
+        self.write("for ");
+        if retains_variable {
+            self.write("let ");
+        }
+        self.write(&format!("{} from ", variable.value));
+        self.visit_expression(start);
+        self.write(" to ");
+        self.visit_expression(end);
+        if let Some(step) = step {
+            self.write(" step ");
+            self.visit_expression(step);
+        }
+        self.write(" do\n");
+        self.write_block_end(body);
+    }
+
+    fn visit_repeat_statement(&mut self, count: &Expression, body: &Statement) {
+        self.write("repeat ");
+        self.visit_expression(count);
+        self.write(" times\n");
+        self.write_block_end(body);
+    }
+
+    fn visit_when_statement(&mut self, subject: &Expression, arms: &[(Expression, Statement)], default: Option<&Statement>) {
+        self.write("when ");
+        self.visit_expression(subject);
+        self.write("\n");
+
+        // Arms are indented under 'when', and their bodies one level further
+        self.indent_level += 1;
+        for (value, body) in arms {
+            self.write_indent();
+            self.write("is ");
+            self.visit_expression(value);
+            self.write(" then\n");
+            self.visit_statement(body);
+        }
+        if let Some(default) = default {
+            self.write_indent();
+            self.write("otherwise\n");
+            self.visit_statement(default);
+        }
+        self.indent_level -= 1;
+
+        self.write_indent();
+        self.write("end");
+    }
+
+    fn visit_function_definition(&mut self, name: &Token, arguments: &[Parameter], return_type: Option<&Type>, body: &Statement) {
+        self.write(&format!("define function {}", name.value));
+        if !arguments.is_empty() {
+            let arguments = arguments.iter().map(|argument| match &argument.type_annotation {
+                Some(annotation) => format!("{}: {}", argument.name.value, annotation),
+                None => argument.name.value.clone(),
+            }).collect::<Vec<_>>();
+            self.write(&format!(" with {}", arguments.join(", ")));
+        }
+        if let Some(return_type) = return_type {
+            self.write(&format!(" returns {}", return_type));
+        }
+        self.write(" as\n");
+        self.write_block_end(body);
+    }
+
+    fn visit_function_call(&mut self, function_name: &Token, arguments: &[Expression]) {
+        self.write(&format!("{}(", function_name.value));
+        self.write_expressions_list(arguments);
+        self.write(")");
+    }
+
+    fn visit_return_statement(&mut self, _span: TextSpan, expression: &Option<Expression>) {
+        self.write("return");
+        if let Some(expression) = expression {
+            self.write(" (");
+            self.visit_expression(expression);
+            self.write(")");
+        }
+    }
+
+    fn block_statement_on_enter(&mut self) {
+        self.indent_level += 1;
+    }
+
+    fn block_statement_on_exit(&mut self) {
+        self.indent_level -= 1;
+    }
+
+    fn visit_number_expression(&mut self, value: i64) {
+        self.write(&value.to_string());
+    }
+
+    fn visit_boolean_expression(&mut self, value: bool) {
+        self.write(&value.to_string());
+    }
+
+    fn visit_variable_expression(&mut self, name: &Token) {
+        self.write(&name.value);
+    }
+
+    fn visit_binary_operation(&mut self, left: &Expression, operator: &BinaryOperator, right: &Expression) {
+        let precedence = operator.precedence();
+
+        // An equal precedence needs parentheses on the side the operator doesn't group from
+        let (left_needs_parens, right_needs_parens) = if operator.is_right_associative() {
+            (
+                binary_precedence(left).is_some_and(|left_precedence| left_precedence <= precedence),
+                binary_precedence(right).is_some_and(|right_precedence| right_precedence < precedence),
+            )
+        } else {
+            (
+                binary_precedence(left).is_some_and(|left_precedence| left_precedence < precedence),
+                binary_precedence(right).is_some_and(|right_precedence| right_precedence <= precedence),
+            )
+        };
+
+        self.write_operand(left, left_needs_parens || is_conditional(left));
+        self.write(&format!(" {} ", operator));
+        self.write_operand(right, right_needs_parens || is_conditional(right));
+    }
+
+    fn visit_unary_operation(&mut self, operator: &UnaryOperator, operand: &Expression) {
+        let operand = ungrouped(operand);
+        match operator {
+            UnaryOperator::Not => self.write("not "),
+            // A space keeps `- -x` from being written as `--x`
+            UnaryOperator::Negate if starts_with_minus(operand) => self.write("- "),
+            UnaryOperator::Negate => self.write("-"),
+        }
+        self.write_operand(operand, matches!(operand, Expression::BinaryOperation { .. } | Expression::Conditional { .. }));
+    }
+
+    fn visit_list_expression(&mut self, elements: &[Expression]) {
+        self.write("[");
+        self.write_expressions_list(elements);
+        self.write("]");
+    }
+
+    fn visit_index_expression(&mut self, target: &Expression, index: &Expression) {
+        let target_needs_parens = matches!(ungrouped(target), Expression::BinaryOperation { .. } | Expression::UnaryOperation { .. } | Expression::Conditional { .. });
+        self.write_operand(target, target_needs_parens);
+        self.write("[");
+        self.visit_expression(index);
+        self.write("]");
+    }
+
+    fn visit_conditional_expression(&mut self, condition: &Expression, then_expr: &Expression, else_expr: &Expression) {
+        self.write("if ");
+        self.visit_expression(condition);
+        self.write(" then ");
+        self.visit_expression(then_expr);
+        self.write(" else ");
+        self.visit_expression(else_expr);
+    }
+
+    fn visit_error_expression(&mut self) {
+    }
+}
+
+fn ungrouped(expression: &Expression) -> &Expression {
+    match expression {
+        Expression::Grouped(inner) => ungrouped(inner),
+        _ => expression,
+    }
+}
+
+// A conditional's else branch would swallow whatever follows it, so it is always parenthesized as an operand
+fn is_conditional(expression: &Expression) -> bool {
+    matches!(ungrouped(expression), Expression::Conditional { .. })
+}
+
+fn binary_precedence(expression: &Expression) -> Option<u8> {
+    match ungrouped(expression) {
+        Expression::BinaryOperation { operator, .. } => Some(operator.precedence()),
+        _ => None,
+    }
+}
+
+fn starts_with_minus(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::UnaryOperation { operator: UnaryOperator::Negate, .. } | Expression::Literal { value: Literal::Number(..0), .. }
+    )
+}
+
+fn starts_with_literal(expression: &Expression) -> bool {
+    match expression {
+        Expression::Literal { .. } => true,
+        Expression::Grouped(inner) => starts_with_literal(inner),
+        Expression::BinaryOperation { left, .. } => starts_with_literal(left),
+        _ => false,
+    }
+}
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::{collections::{BTreeMap, HashMap, HashSet}, fmt};
 
-use crate::ast::{expression::{BinaryOperator, UnaryOperator}, statement::Statement, Ast, AstExplorer};
+use crate::{ast::{expression::{BinaryOperator, Expression, UnaryOperator}, statement::Statement, Ast, AstExplorer}, compiler::CompilerOptions, lexer::TextSpan, types::Type};
 
 mod builtin;
+pub mod vm;
 
 
 static BINARY_OPERATORS: &[(BinaryOperator, RuntimeBinaryOperator)] = &[
@@ -11,6 +12,7 @@ static BINARY_OPERATORS: &[(BinaryOperator, RuntimeBinaryOperator)] = &[
     (BinaryOperator::Multiply, builtin::mul),
     (BinaryOperator::Divide, builtin::div),
     (BinaryOperator::Modulus, builtin::modulus),
+    (BinaryOperator::Power, builtin::pow),
     (BinaryOperator::Equal, builtin::eq),
     (BinaryOperator::NotEqual, builtin::not_eq),
     (BinaryOperator::GreaterThan, builtin::gt),
@@ -18,6 +20,12 @@ static BINARY_OPERATORS: &[(BinaryOperator, RuntimeBinaryOperator)] = &[
     (BinaryOperator::LessThan, builtin::lt),
     (BinaryOperator::LessThanOrEqual, builtin::lt_eq),
 
+    (BinaryOperator::BitAnd, builtin::bit_and),
+    (BinaryOperator::BitOr, builtin::bit_or),
+    (BinaryOperator::BitXor, builtin::bit_xor),
+    (BinaryOperator::ShiftLeft, builtin::shift_left),
+    (BinaryOperator::ShiftRight, builtin::shift_right),
+
     (BinaryOperator::And, builtin::and),
     (BinaryOperator::Or, builtin::or),
 ];
@@ -27,20 +35,60 @@ static UNARY_OPERATORS: &[(UnaryOperator, RuntimeUnaryOperator)] = &[
     (UnaryOperator::Not, builtin::not)
 ];
 
+// A function provided by the runtime, with the signature the resolver checks its calls against
+pub(crate) struct NativeFunction {
+    pub(crate) name: &'static str,
+    pub(crate) parameters: Vec<(&'static str, Type)>,
+    pub(crate) return_type: Type,
+    function: RuntimeNativeFunction,
+}
+
+// The only place native functions are declared, shared by the resolver and the runtimes
+pub(crate) fn native_functions() -> Vec<NativeFunction> {
+    vec![
+        NativeFunction {
+            name: "len",
+            // A list of any element type
+            parameters: vec![("list", Type::List(Box::new(Type::Unresolved)))],
+            return_type: Type::Int,
+            function: builtin::len,
+        },
+        NativeFunction {
+            name: "clamp",
+            parameters: vec![("value", Type::Int), ("lo", Type::Int), ("hi", Type::Int)],
+            return_type: Type::Int,
+            function: builtin::clamp,
+        },
+        NativeFunction {
+            name: "sign",
+            parameters: vec![("value", Type::Int)],
+            return_type: Type::Int,
+            function: builtin::sign,
+        },
+    ]
+}
+
+
+type RuntimeBinaryOperator = fn (RuntimeValue, RuntimeValue, &CompilerOptions) -> Result<RuntimeValue, RuntimeError>;
+type RuntimeUnaryOperator = fn (RuntimeValue, &CompilerOptions) -> Result<RuntimeValue, RuntimeError>;
+type RuntimeNativeFunction = fn (Vec<RuntimeValue>) -> Result<RuntimeValue, RuntimeError>;
 
-type RuntimeBinaryOperator = fn (RuntimeValue, RuntimeValue) -> Result<RuntimeValue, RuntimeError>;
-type RuntimeUnaryOperator = fn (RuntimeValue) -> Result<RuntimeValue, RuntimeError>;
+type VariableChangeCallback = Box<dyn FnMut(&str, &RuntimeValue)>;
+type StatementCallback = Box<dyn FnMut(&Statement)>;
+type ExpressionCallback = Box<dyn FnMut(&Expression)>;
 
 struct RuntimeFunctionsDispatcher {
     binary_operators: HashMap<BinaryOperator, RuntimeBinaryOperator>,
     unary_operators: HashMap<UnaryOperator, RuntimeUnaryOperator>,
+    native_functions: HashMap<&'static str, RuntimeNativeFunction>,
 }
 
 impl RuntimeFunctionsDispatcher {
     fn new() -> Self {
         Self {
-            binary_operators: BINARY_OPERATORS.iter().map(|op| *op).collect(),
-            unary_operators: UNARY_OPERATORS.iter().map(|op| *op).collect(),
+            binary_operators: BINARY_OPERATORS.iter().copied().collect(),
+            unary_operators: UNARY_OPERATORS.iter().copied().collect(),
+            native_functions: native_functions().into_iter().map(|native| (native.name, native.function)).collect(),
         }
     }
 
@@ -51,20 +99,36 @@ impl RuntimeFunctionsDispatcher {
     fn get_unary_operator_function(&self, operator: &UnaryOperator) -> Option<&RuntimeUnaryOperator> {
         self.unary_operators.get(operator)
     }
+
+    fn get_native_function(&self, name: &str) -> Option<&RuntimeNativeFunction> {
+        self.native_functions.get(name)
+    }
 }
 
 
 struct RuntimeScope {
-    variables: HashMap<String, RuntimeValue>,
+    // Ordered by name so that anything listing variables is reproducible, at the cost of O(log n) lookups
+    variables: BTreeMap<String, RuntimeValue>,
+    constants: HashSet<String>,
 }
 
 impl RuntimeScope {
     fn new() -> Self {
         Self {
-            variables: HashMap::new(),
+            variables: BTreeMap::new(),
+            constants: HashSet::new(),
         }
     }
 
+    fn set_constant(&mut self, name: String, value: RuntimeValue) {
+        self.constants.insert(name.clone());
+        self.variables.insert(name, value);
+    }
+
+    fn is_constant(&self, name: &str) -> bool {
+        self.constants.contains(name)
+    }
+
 
     fn set_variable(&mut self, name: String, value: RuntimeValue) {
         self.variables.insert(name, value);
@@ -76,17 +140,93 @@ impl RuntimeScope {
 
 }
 
-#[derive(Clone, Debug)]
-enum RuntimeValue {
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuntimeValue {
     Number(i64),
     Bool(bool),
+    List(Vec<RuntimeValue>),
+    // The result of a function that returns no value
+    Unit,
 }
 
-enum RuntimeError {
+// The canonical text of a value, shared by everything that shows values to the user
+impl fmt::Display for RuntimeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeValue::Number(value) => write!(f, "{}", value),
+            RuntimeValue::Bool(value) => write!(f, "{}", value),
+            RuntimeValue::Unit => write!(f, "unit"),
+            RuntimeValue::List(elements) => {
+                write!(f, "[")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RuntimeError {
     VariableNotFound(String),
+    FunctionNotFound(String),
+    AssignmentToConstant(String),
     InvalidOperation,
     DivisionByZero,
-    InvalidCondition,
+    IntegerOverflow,
+    InvalidClampRange { lo: i64, hi: i64 },
+    ZeroStep,
+    InvalidShift { amount: i64, bits: u32 },
+    NegativeExponent(i64),
+    InvalidCondition { span: TextSpan },
+    IndexOutOfBounds { index: i64, length: usize },
+    LoopLimitExceeded(u64),
+    ArgumentCountMismatch { expected: usize, found: usize },
+    // An error raised while running the statement or expression at `span`
+    At { span: TextSpan, error: Box<RuntimeError> },
+}
+
+impl RuntimeError {
+    pub fn span(&self) -> Option<&TextSpan> {
+        match self {
+            RuntimeError::InvalidCondition { span } | RuntimeError::At { span, .. } => Some(span),
+            _ => None,
+        }
+    }
+
+    // Locates the error at `span`, unless it already carries a more precise location
+    pub fn at(self, span: TextSpan) -> RuntimeError {
+        match self.span() {
+            Some(_) => self,
+            None => RuntimeError::At { span, error: Box::new(self) },
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::VariableNotFound(name) => write!(f, "variable '{}' not found", name),
+            RuntimeError::FunctionNotFound(name) => write!(f, "function '{}' not found", name),
+            RuntimeError::AssignmentToConstant(name) => write!(f, "cannot assign to constant '{}'", name),
+            RuntimeError::InvalidOperation => write!(f, "invalid operation"),
+            RuntimeError::DivisionByZero => write!(f, "division by zero"),
+            RuntimeError::IntegerOverflow => write!(f, "integer overflow"),
+            RuntimeError::InvalidClampRange { lo, hi } => write!(f, "clamp range is empty, lower bound {} is greater than upper bound {}", lo, hi),
+            RuntimeError::ZeroStep => write!(f, "the step of a for loop cannot be 0"),
+            RuntimeError::InvalidShift { amount, bits } => write!(f, "cannot shift by {} bits, the amount must be between 0 and {}", amount, bits - 1),
+            RuntimeError::NegativeExponent(exponent) => write!(f, "integer exponent cannot be negative, found {}", exponent),
+            RuntimeError::InvalidCondition { span } => write!(f, "at {}:{}: condition must be a boolean", span.start.line, span.start.column),
+            RuntimeError::IndexOutOfBounds { index, length } => write!(f, "index {} is out of bounds for a list of length {}", index, length),
+            RuntimeError::LoopLimitExceeded(limit) => write!(f, "loop exceeded the limit of {} iterations", limit),
+            RuntimeError::ArgumentCountMismatch { expected, found } => write!(f, "expected {} arguments, found {}", expected, found),
+            RuntimeError::At { span, error } => write!(f, "at {}:{}: {}", span.start.line, span.start.column, error),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -100,51 +240,168 @@ pub struct Interpreter {
     accumulator: Option<RuntimeValue>,
     scopes: Vec<RuntimeScope>,
     dispatcher: RuntimeFunctionsDispatcher,
+    options: CompilerOptions,
     functions: HashMap<String, FunctionInfo>,
     stop_execution: bool,
+    variable_change_callback: Option<VariableChangeCallback>,
+    statement_callback: Option<StatementCallback>,
+    expression_callback: Option<ExpressionCallback>,
+    // Span of the statement or expression being evaluated, where runtime errors are reported
+    current_span: Option<TextSpan>,
+    // First runtime error of the run. Once set, the remaining statements are skipped up to the caller of the run.
+    error: Option<RuntimeError>,
+    // Iterations a single while or for loop may run before failing, None for no limit
+    max_loop_iterations: Option<u64>,
 }
 
 impl Interpreter {
-    fn new() -> Self {
+    pub fn new() -> Self {
+        Self::with_options(CompilerOptions::default())
+    }
+
+    pub fn with_options(options: CompilerOptions) -> Self {
         Interpreter {
             accumulator: None,
             scopes: vec![RuntimeScope::new()],
             dispatcher: RuntimeFunctionsDispatcher::new(),
+            options,
             functions: HashMap::new(),
             stop_execution: false,
+            variable_change_callback: None,
+            statement_callback: None,
+            expression_callback: None,
+            current_span: None,
+            error: None,
+            max_loop_iterations: None,
         }
     }
 
+    // An interpreter that calls `callback` before executing each statement
+    pub fn with_trace(callback: StatementCallback) -> Self {
+        let mut interpreter = Self::new();
+        interpreter.on_statement(callback);
+        interpreter
+    }
+
     pub fn interpret(ast: &Ast) {
         let mut interpreter = Self::new();
 
-        interpreter.collect_functions(ast);
+        interpreter.run_program(ast);
+
+        interpreter.display_state();
+
+    }
 
+    // Calls `main` when the program defines one at the top level, otherwise runs the top-level statements in order.
+    // With a `main`, the top-level declarations still run first so `main` can use them as globals;
+    // the other top-level statements are not run.
+    pub fn try_run_program(&mut self, ast: &Ast) -> Result<(), RuntimeError> {
+        self.collect_functions(ast);
+
+        match self.functions.get("main").cloned() {
+            Some(main) => {
+                for statement in ast.statements().iter().filter(|statement| statement.is_declaration()) {
+                    self.visit_statement(statement);
+                }
+                self.call_function(main, &[]);
+            }
+            None => self.explore_ast(ast),
+        }
+        self.take_error()
+    }
+
+    // Runs the top-level statements in order, even when the program defines `main`
+    pub fn try_run(&mut self, ast: &Ast) -> Result<(), RuntimeError> {
+        self.collect_functions(ast);
+        self.explore_ast(ast);
+        self.take_error()
+    }
+
+    // Like `try_run_program`, stopping the process with the error's message when the program fails
+    pub fn run_program(&mut self, ast: &Ast) {
+        if let Err(error) = self.try_run_program(ast) {
+            self.without_backtrace(|_| panic!("Error: {}", error));
+        }
+    }
+
+    pub fn run(&mut self, ast: &Ast) {
+        if let Err(error) = self.try_run(ast) {
+            self.without_backtrace(|_| panic!("Error: {}", error));
+        }
+    }
+
+    fn take_error(&mut self) -> Result<(), RuntimeError> {
+        self.stop_execution = false;
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn without_backtrace(&mut self, execute: impl FnOnce(&mut Self)) {
         let rust_backtrace = env!("RUST_BACKTRACE");
 
         unsafe {std::env::set_var("RUST_BACKTRACE", "0")};
-        interpreter.explore_ast(ast);
+        execute(self);
         unsafe {std::env::set_var("RUST_BACKTRACE", rust_backtrace)};
+    }
 
-        interpreter.display_state();
+    // Registers a callback fired whenever a variable is declared or assigned
+    pub fn on_variable_change(&mut self, callback: VariableChangeCallback) {
+        self.variable_change_callback = Some(callback);
+    }
+
+    // Registers a callback fired before each statement runs, blocks and function bodies included
+    pub fn on_statement(&mut self, callback: StatementCallback) {
+        self.statement_callback = Some(callback);
+    }
+
+    // Registers a callback fired before each expression is evaluated
+    pub fn on_expression(&mut self, callback: ExpressionCallback) {
+        self.expression_callback = Some(callback);
+    }
+
+    // Turns a loop that never ends into a runtime error, which helps debugging
+    pub fn set_max_loop_iterations(&mut self, limit: Option<u64>) {
+        self.max_loop_iterations = limit;
+    }
 
+    pub fn globals(&self) -> &BTreeMap<String, RuntimeValue> {
+        &self.scopes[0].variables
     }
 
     pub fn display_state(&self) {
-        println!("Current Variables:");
-        for (name, value) in &self.scopes[0].variables {
-            match value {
-                RuntimeValue::Number(n) => println!("{}: {}", name, n),
-                RuntimeValue::Bool(b) => println!("{}: {}", name, b),
+        print!("{}", self.dump_state(false));
+    }
+
+    // Prints every live scope, each nested one indented under its parent
+    pub fn display_scopes(&self) {
+        print!("{}", self.dump_state(true));
+    }
+
+    pub fn dump_state(&self, all_scopes: bool) -> String {
+        let scopes = if all_scopes { &self.scopes[..] } else { &self.scopes[..1] };
+
+        let mut output = String::from("Current Variables:\n");
+        for (depth, scope) in scopes.iter().enumerate() {
+            let mut indent = String::new();
+            if all_scopes {
+                output.push_str(&format!("{}scope {}:\n", "  ".repeat(depth), depth));
+                indent = "  ".repeat(depth + 1);
+            }
+
+            for (name, value) in &scope.variables {
+                output.push_str(&format!("{}{}: {}\n", indent, name, value));
             }
         }
+        output
     }
 
     fn collect_functions(&mut self, ast: &Ast) {
         for statement in ast.statements() {
-            if let Statement::FunctionDefinition { name, arguments, body } = statement {
+            if let Statement::FunctionDefinition { name, arguments, body, .. } = statement {
                 let function_info = FunctionInfo {
-                    parameters: arguments.iter().map(|arg| arg.value.clone()).collect(),
+                    parameters: arguments.iter().map(|arg| arg.name.value.clone()).collect(),
                     body: *body.clone(),
                 };
                 self.functions.insert(name.value.clone(), function_info);
@@ -154,8 +411,12 @@ impl Interpreter {
     }
 
     fn call_function(&mut self, function_info: FunctionInfo, arguments: &[crate::ast::expression::Expression]) {
-        
-        
+        // The resolver rejects these calls, but an AST can be run without being resolved
+        if arguments.len() != function_info.parameters.len() {
+            self.report_error(RuntimeError::ArgumentCountMismatch { expected: function_info.parameters.len(), found: arguments.len() });
+            return;
+        }
+
         let parameters = function_info.parameters
         .iter()
         .zip(arguments)
@@ -171,56 +432,119 @@ impl Interpreter {
         }
 
         self.visit_statement(&function_info.body);
+        // Running off the end of the body returns no value
+        if !self.stop_execution {
+            self.accumulator = Some(RuntimeValue::Unit);
+        }
         self.stop_execution = false;
 
         self.pop_scope();
     }
 
+    // Loop bounds, steps and counters must be integers, None once an error was reported
+    fn evaluate_integer(&mut self, expression: &Expression) -> Option<i64> {
+        self.visit_expression(expression);
+        match self.get_accumulator_value() {
+            RuntimeValue::Number(value) => Some(value),
+            _ => {
+                self.report_error(RuntimeError::InvalidOperation.at(expression.span()));
+                None
+            }
+        }
+    }
+
+    fn get_loop_variable(&mut self, variable: &crate::lexer::Token) -> Option<i64> {
+        match self.get_variable(&variable.value) {
+            RuntimeValue::Number(value) => Some(value),
+            _ => {
+                self.report_error(RuntimeError::InvalidOperation);
+                None
+            }
+        }
+    }
+
+    fn check_loop_iterations(&mut self, iterations: u64) {
+        if let Some(limit) = self.max_loop_iterations
+            && iterations > limit {
+            self.report_error(RuntimeError::LoopLimitExceeded(limit));
+        }
+    }
+
     fn get_accumulator_value(&mut self) -> RuntimeValue {
-        self.accumulator.take().expect("Expression unevaluated")
+        match self.accumulator.take() {
+            Some(value) => value,
+            // After an error the evaluation only unwinds, so this value is never stored
+            None if self.has_failed() => RuntimeValue::Unit,
+            None => panic!("Expression unevaluated"),
+        }
+    }
+
+    fn has_failed(&self) -> bool {
+        self.error.is_some()
     }
 
     fn register_variable(&mut self, name: String, value: RuntimeValue) {
-        
+        if self.has_failed() {
+            return;
+        }
+        self.notify_variable_change(&name, &value);
         self.scopes.last_mut().unwrap().set_variable(name, value);
 
     }
 
+    fn register_constant(&mut self, name: String, value: RuntimeValue) {
+        if self.has_failed() {
+            return;
+        }
+        self.notify_variable_change(&name, &value);
+        self.scopes.last_mut().unwrap().set_constant(name, value);
+    }
+
     fn set_variable_value(&mut self, name: String, value: RuntimeValue) {
-        if let Some(scope) = 
-            self.scopes
-                .iter_mut()
-                .rev()
-                .find(|s| s.get_variable(&name).is_some()) 
-        {
-            scope.set_variable(name, value);
-        } 
-        else {
+        if self.has_failed() {
+            return;
+        }
+        let Some(index) = self.scopes.iter().rposition(|s| s.get_variable(&name).is_some()) else {
             self.report_error(RuntimeError::VariableNotFound(name));
+            return;
+        };
+
+        if self.scopes[index].is_constant(&name) {
+            self.report_error(RuntimeError::AssignmentToConstant(name));
+            return;
         }
+        self.notify_variable_change(&name, &value);
+        self.scopes[index].set_variable(name, value);
     }
 
-    fn get_variable(&self, name: &str) -> &RuntimeValue {
+    fn notify_variable_change(&mut self, name: &str, value: &RuntimeValue) {
+        if let Some(callback) = self.variable_change_callback.as_mut() {
+            callback(name, value);
+        }
+    }
+
+    fn get_variable(&mut self, name: &str) -> RuntimeValue {
         let value = self.scopes
             .iter()
             .rev()
-            .find_map(|scope| scope.get_variable(name));
+            .find_map(|scope| scope.get_variable(name))
+            .cloned();
 
-        match value {
-            Some(v) => v,
-            None => {
-                self.report_error(RuntimeError::VariableNotFound(name.to_string()));
-            }
-        }
+        value.unwrap_or_else(|| {
+            self.report_error(RuntimeError::VariableNotFound(name.to_string()));
+            RuntimeValue::Unit
+        })
     }
 
-    fn report_error(&self, error: RuntimeError) -> ! {
-        match error {
-            RuntimeError::VariableNotFound(name) => panic!("Variable not found: {}", name),
-            RuntimeError::DivisionByZero => panic!("Error: Division by zero"),
-            RuntimeError::InvalidCondition => panic!("Error: condition in if block must be a boolean"),
-            RuntimeError::InvalidOperation => panic!("Error: invalid operation"),
+    // Keeps the first error, located at the statement or expression being run unless it carries its own span
+    fn report_error(&mut self, error: RuntimeError) {
+        if self.has_failed() {
+            return;
         }
+        self.error = Some(match self.current_span.clone() {
+            Some(span) => error.at(span),
+            None => error,
+        });
     }
 
     fn push_scope(&mut self) {
@@ -232,20 +556,49 @@ impl Interpreter {
     }
 }
 
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AstExplorer for Interpreter {
 
     fn visit_statement(&mut self, statement: &Statement) {
-        if !self.stop_execution {
+        if !self.stop_execution && !self.has_failed() {
+            if let Some(callback) = self.statement_callback.as_mut() {
+                callback(statement);
+            }
+            let enclosing_span = self.current_span.replace(statement.span());
             self.visit_statement_impl(statement);
+            self.current_span = enclosing_span;
         }
     }
 
-    fn visit_variable_declaration(&mut self, name: &crate::lexer::Token, value: &crate::ast::expression::Expression) {
+    fn visit_expression(&mut self, expression: &Expression) {
+        if self.has_failed() {
+            return;
+        }
+        if let Some(callback) = self.expression_callback.as_mut() {
+            callback(expression);
+        }
+        let enclosing_span = self.current_span.replace(expression.span());
+        self.visit_expression_impl(expression);
+        self.current_span = enclosing_span;
+    }
+
+    fn visit_variable_declaration(&mut self, name: &crate::lexer::Token, _type_annotation: Option<&crate::types::Type>, value: &crate::ast::expression::Expression) {
         self.visit_expression(value);
         let expr_value = self.get_accumulator_value();
         self.register_variable(name.value.clone(), expr_value);
     }
 
+    fn visit_const_declaration(&mut self, name: &crate::lexer::Token, value: &crate::ast::expression::Expression) {
+        self.visit_expression(value);
+        let expr_value = self.get_accumulator_value();
+        self.register_constant(name.value.clone(), expr_value);
+    }
+
     fn visit_variable_assignement(&mut self, name: &crate::lexer::Token, value: &crate::ast::expression::Expression) {
 
         self.visit_expression(value);
@@ -254,12 +607,19 @@ impl AstExplorer for Interpreter {
     }
 
 
+    fn visit_swap_statement(&mut self, left: &crate::lexer::Token, right: &crate::lexer::Token) {
+        let left_value = self.get_variable(&left.value);
+        let right_value = self.get_variable(&right.value);
+        self.set_variable_value(left.value.clone(), right_value);
+        self.set_variable_value(right.value.clone(), left_value);
+    }
+
     fn visit_number_expression(&mut self, value: i64) {
         self.accumulator = Some(RuntimeValue::Number(value));
     }
 
     fn visit_variable_expression(&mut self, name: &crate::lexer::Token) {
-        self.accumulator = Some(self.get_variable(&name.value).clone());
+        self.accumulator = Some(self.get_variable(&name.value));
     }
 
     fn visit_binary_operation(&mut self, left: &crate::ast::expression::Expression, operator: &crate::ast::expression::BinaryOperator, right: &crate::ast::expression::Expression) {
@@ -274,7 +634,7 @@ impl AstExplorer for Interpreter {
             .get_binary_operator_function(operator)
             .unwrap();
 
-        match op(left_value, right_value) {
+        match op(left_value, right_value, &self.options) {
             Ok(result) => self.accumulator = Some(result),
             Err(error) => self.report_error(error),
         }
@@ -288,7 +648,7 @@ impl AstExplorer for Interpreter {
             .get_unary_operator_function(operator)
             .unwrap();
 
-        match op(operand_value) {
+        match op(operand_value, &self.options) {
             Ok(result) => self.accumulator = Some(result),
             Err(error) => self.report_error(error),
         }
@@ -308,7 +668,7 @@ impl AstExplorer for Interpreter {
             },
 
             _ => {
-                self.report_error(RuntimeError::InvalidCondition);
+                self.report_error(RuntimeError::InvalidCondition { span: condition.span() });
             }
         }
 
@@ -327,82 +687,200 @@ impl AstExplorer for Interpreter {
     }
     
     fn visit_while_statement(&mut self, condition: &crate::ast::expression::Expression, body: &crate::ast::statement::Statement) {
+        let mut iterations = 0;
         loop {
             self.visit_expression(condition);
             let condition_value = self.get_accumulator_value();
 
             match condition_value {
                 RuntimeValue::Bool(true) => {
+                    iterations += 1;
+                    self.check_loop_iterations(iterations);
                     self.visit_statement(body);
                 }
                 RuntimeValue::Bool(false) => {
                     break;
                 }
                 _ => {
-                    self.report_error(RuntimeError::InvalidCondition);
+                    self.report_error(RuntimeError::InvalidCondition { span: condition.span() });
                 }
             }
+
+            if self.has_failed() {
+                break;
+            }
         }
     }
     
-    fn visit_for_statement(&mut self, variable: &crate::lexer::Token, start: &crate::ast::expression::Expression, end: &crate::ast::expression::Expression, step: &Option<crate::ast::expression::Expression>, body: &crate::ast::statement::Statement) {
-        self.visit_expression(start);
-        let start_value = self.get_accumulator_value();
+    fn visit_repeat_statement(&mut self, count: &crate::ast::expression::Expression, body: &crate::ast::statement::Statement) {
+        // The count is evaluated once, so changing it inside the body doesn't affect the loop
+        self.visit_expression(count);
+        let count_value = match self.get_accumulator_value() {
+            RuntimeValue::Number(count) => count,
+            _ => {
+                self.report_error(RuntimeError::InvalidOperation);
+                return;
+            }
+        };
+
+        for _ in 0..count_value.max(0) {
+            if self.has_failed() {
+                break;
+            }
+            self.visit_statement(body);
+        }
+    }
 
-        self.visit_expression(end);
-        let end_value = self.get_accumulator_value();
+    fn visit_when_statement(&mut self, subject: &crate::ast::expression::Expression, arms: &[(crate::ast::expression::Expression, crate::ast::statement::Statement)], default: Option<&crate::ast::statement::Statement>) {
+        self.visit_expression(subject);
+        let subject_value = self.get_accumulator_value();
 
-        let step_value = if let Some(step_expr) = step {
-            self.visit_expression(step_expr);
-            self.get_accumulator_value()
-        } else {
-            RuntimeValue::Number(1) // Default step value
+        // Arm values are evaluated in order, up to the first one equal to the subject
+        for (value, body) in arms {
+            self.visit_expression(value);
+            if self.get_accumulator_value() == subject_value {
+                self.visit_statement(body);
+                return;
+            }
+        }
+
+        if let Some(default) = default {
+            self.visit_statement(default);
+        }
+    }
+
+    fn visit_for_statement(&mut self, variable: &crate::lexer::Token, retains_variable: bool, start: &crate::ast::expression::Expression, end: &crate::ast::expression::Expression, step: &Option<crate::ast::expression::Expression>, body: &crate::ast::statement::Statement) {
+        let Some(start_value) = self.evaluate_integer(start) else { return };
+        let Some(end_value) = self.evaluate_integer(end) else { return };
+        let step_value = match step {
+            Some(step_expr) => match self.evaluate_integer(step_expr) {
+                Some(step_value) => step_value,
+                None => return,
+            },
+            None => 1, // Default step value
         };
 
-        self.push_scope();
-        self.register_variable(variable.value.clone(), start_value);
+        if step_value == 0 {
+            self.report_error(RuntimeError::ZeroStep);
+            return;
+        }
 
-        loop {
-            let current_value = self.get_variable(&variable.value);
-            let exit = builtin::gt(current_value.clone(), end_value.clone());
-            match exit {
-                Ok(RuntimeValue::Bool(true)) => {
-                    break;
-                },
+        // Both bounds are inclusive: an ascending loop stops once past `end`, a descending one once below it.
+        // Values are compared in i128, where stepping past an `end` near the integer limits cannot overflow.
+        let is_past_end = |value: i128| if step_value < 0 {
+            value < i128::from(end_value)
+        } else {
+            value > i128::from(end_value)
+        };
 
-                Err(err) => {
-                    self.report_error(err);
-                }
-                _ => {}
+        // A retained variable lives in the enclosing scope and keeps the first value past `end`
+        if !retains_variable {
+            self.push_scope();
+        }
+        self.register_variable(variable.value.clone(), RuntimeValue::Number(start_value));
+
+        let mut iterations = 0;
+        while let Some(current_value) = self.get_loop_variable(variable) {
+            if is_past_end(i128::from(current_value)) {
+                break;
             }
 
+            iterations += 1;
+            self.check_loop_iterations(iterations);
             self.visit_statement(body);
 
-            let current_value = self.get_variable(&variable.value);
-            let new_value = match builtin::add(current_value.clone(), step_value.clone()) {
-                Ok(value) => value, 
-                Err(err) => self.report_error(err)
+            // The body may assign the variable, so the next value steps from whatever it holds now
+            let Some(current_value) = self.get_loop_variable(variable) else { break };
+            let next_value = i128::from(current_value) + i128::from(step_value);
+            // Past `end` the loop is over, so a value outside the integer width only stops it, keeping the last value
+            if self.has_failed() || (is_past_end(next_value) && !self.options.fits_integer(next_value)) {
+                break;
+            }
+            let Some(next_value) = self.options.fit_integer(next_value) else {
+                self.report_error(RuntimeError::IntegerOverflow);
+                break;
             };
-            self.set_variable_value(variable.value.clone(), new_value);
+            self.set_variable_value(variable.value.clone(), RuntimeValue::Number(next_value));
         }
 
-        self.pop_scope();
+        if !retains_variable {
+            self.pop_scope();
+        }
     }
     
-    fn visit_function_definition(&mut self, _name: &crate::lexer::Token, _arguments: &[crate::lexer::Token], _body: &crate::ast::statement::Statement) {
+    fn visit_function_definition(&mut self, _name: &crate::lexer::Token, _arguments: &[crate::ast::statement::Parameter], _return_type: Option<&crate::types::Type>, _body: &crate::ast::statement::Statement) {
     }
     fn visit_function_call(&mut self, function_name: &crate::lexer::Token, arguments: &[crate::ast::expression::Expression]) {
         if let Some(function_info) = self.functions.get(&function_name.value) {
             let function_info = function_info.clone();
             self.call_function(function_info, arguments);
         }
+        else if let Some(native_function) = self.dispatcher.get_native_function(&function_name.value).copied() {
+            let values = arguments
+            .iter()
+            .map(|arg| {
+                self.visit_expression(arg);
+                self.get_accumulator_value()
+            }).collect();
+
+            match native_function(values) {
+                Ok(value) => self.accumulator = Some(value),
+                Err(err) => self.report_error(err),
+            }
+        }
+        // Only reachable when the AST was not resolved, the resolver reports undefined functions
+        else {
+            self.report_error(RuntimeError::FunctionNotFound(function_name.value.clone()));
+        }
     }
     
     fn visit_return_statement(&mut self, _span: crate::lexer::TextSpan, expression: &Option<crate::ast::expression::Expression>) {
-        if let Some(expr) = expression {
-            self.visit_expression(expr);
+        match expression {
+            Some(expr) => self.visit_expression(expr),
+            None => self.accumulator = Some(RuntimeValue::Unit),
         }
 
         self.stop_execution = true;
     }
+
+    fn visit_list_expression(&mut self, elements: &[crate::ast::expression::Expression]) {
+        let mut values = Vec::with_capacity(elements.len());
+        for element in elements {
+            self.visit_expression(element);
+            values.push(self.get_accumulator_value());
+        }
+
+        self.accumulator = Some(RuntimeValue::List(values));
+    }
+
+    fn visit_index_expression(&mut self, target: &crate::ast::expression::Expression, index: &crate::ast::expression::Expression) {
+        self.visit_expression(target);
+        let target_value = self.get_accumulator_value();
+        self.visit_expression(index);
+        let index_value = self.get_accumulator_value();
+
+        match (target_value, index_value) {
+            (RuntimeValue::List(elements), RuntimeValue::Number(index)) => {
+                match usize::try_from(index).ok().and_then(|i| elements.get(i)) {
+                    Some(value) => self.accumulator = Some(value.clone()),
+                    None => self.report_error(RuntimeError::IndexOutOfBounds { index, length: elements.len() }),
+                }
+            },
+            _ => self.report_error(RuntimeError::InvalidOperation),
+        }
+    }
+
+    fn visit_conditional_expression(&mut self, condition: &crate::ast::expression::Expression, then_expr: &crate::ast::expression::Expression, else_expr: &crate::ast::expression::Expression) {
+        self.visit_expression(condition);
+
+        match self.get_accumulator_value() {
+            RuntimeValue::Bool(true) => self.visit_expression(then_expr),
+            RuntimeValue::Bool(false) => self.visit_expression(else_expr),
+            _ => self.report_error(RuntimeError::InvalidCondition { span: condition.span() }),
+        }
+    }
+
+    // Error nodes only exist in ASTs that failed to parse, which are never run
+    fn visit_error_expression(&mut self) {
+    }
 }
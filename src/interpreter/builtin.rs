@@ -1,39 +1,47 @@
+use crate::compiler::{CompilerOptions, DivisionMode, OverflowMode};
+
 use super::{RuntimeError, RuntimeValue};
 
-pub fn add(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn add(left: RuntimeValue, right: RuntimeValue, options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
-        Ok(RuntimeValue::Number(l + r))
+        options.fit_integer(l as i128 + r as i128).map(RuntimeValue::Number).ok_or(RuntimeError::IntegerOverflow)
     }
     else {
         Err(RuntimeError::InvalidOperation)
     }
 }
 
-pub fn sub(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn sub(left: RuntimeValue, right: RuntimeValue, options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
-        Ok(RuntimeValue::Number(l - r))
+        options.fit_integer(l as i128 - r as i128).map(RuntimeValue::Number).ok_or(RuntimeError::IntegerOverflow)
     }
     else {
         Err(RuntimeError::InvalidOperation)
     }
 }
 
-pub fn mul(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn mul(left: RuntimeValue, right: RuntimeValue, options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
-        Ok(RuntimeValue::Number(l * r))
+        options.fit_integer(l as i128 * r as i128).map(RuntimeValue::Number).ok_or(RuntimeError::IntegerOverflow)
     }
     else {
         Err(RuntimeError::InvalidOperation)
     }
 }
 
-pub fn div(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn div(left: RuntimeValue, right: RuntimeValue, options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
         if r == 0 {
             Err(RuntimeError::DivisionByZero)
         }
         else {
-            Ok(RuntimeValue::Number(l / r))
+            let (l, r) = (l as i128, r as i128);
+            let quotient = match options.division {
+                DivisionMode::Truncate => l / r,
+                DivisionMode::Floor if l % r != 0 && (l < 0) != (r < 0) => l / r - 1,
+                DivisionMode::Floor => l / r,
+            };
+            options.fit_integer(quotient).map(RuntimeValue::Number).ok_or(RuntimeError::IntegerOverflow)
         }
     }
     else {
@@ -41,13 +49,20 @@ pub fn div(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, Runt
     }
 }
 
-pub fn modulus(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn modulus(left: RuntimeValue, right: RuntimeValue, options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
         if r == 0 {
             Err(RuntimeError::DivisionByZero)
         }
         else {
-            Ok(RuntimeValue::Number(l % r))
+            // In floor mode the remainder takes the sign of the divisor
+            let (l, r) = (l as i128, r as i128);
+            let remainder = match options.division {
+                DivisionMode::Truncate => l % r,
+                DivisionMode::Floor if l % r != 0 && (l < 0) != (r < 0) => l % r + r,
+                DivisionMode::Floor => l % r,
+            };
+            options.fit_integer(remainder).map(RuntimeValue::Number).ok_or(RuntimeError::IntegerOverflow)
         }
     }
     else {
@@ -55,16 +70,36 @@ pub fn modulus(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue,
     }
 }
 
-pub fn eq(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn pow(left: RuntimeValue, right: RuntimeValue, options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
+    if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
+        if r < 0 {
+            return Err(RuntimeError::NegativeExponent(r));
+        }
+
+        // Exponents past u32 only matter for a base of -1, 0 or 1, so keeping the parity is enough
+        let exponent = u32::try_from(r).unwrap_or(if r % 2 == 0 { u32::MAX - 1 } else { u32::MAX });
+        let result = match options.overflow {
+            OverflowMode::Wrap => Some((l as i128).wrapping_pow(exponent)),
+            OverflowMode::Error => (l as i128).checked_pow(exponent),
+        };
+        result.and_then(|value| options.fit_integer(value)).map(RuntimeValue::Number).ok_or(RuntimeError::IntegerOverflow)
+    }
+    else {
+        Err(RuntimeError::InvalidOperation)
+    }
+}
+
+pub fn eq(left: RuntimeValue, right: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     match (left, right) {
         (RuntimeValue::Number(l), RuntimeValue::Number(r)) => Ok(RuntimeValue::Bool(l == r)),
         (RuntimeValue::Bool(l), RuntimeValue::Bool(r)) => Ok(RuntimeValue::Bool(l == r)),
+        (RuntimeValue::List(l), RuntimeValue::List(r)) => Ok(RuntimeValue::Bool(l == r)),
 
         _ => Err(RuntimeError::InvalidOperation)
     }
 }
 
-pub fn gt(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn gt(left: RuntimeValue, right: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
         Ok(RuntimeValue::Bool(l > r))
     }
@@ -73,7 +108,7 @@ pub fn gt(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, Runti
     }
 }
 
-pub fn gt_eq(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn gt_eq(left: RuntimeValue, right: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
         Ok(RuntimeValue::Bool(l >= r))
     }
@@ -82,7 +117,7 @@ pub fn gt_eq(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, Ru
     }
 }
 
-pub fn lt(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn lt(left: RuntimeValue, right: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
         Ok(RuntimeValue::Bool(l < r))
     }
@@ -91,7 +126,7 @@ pub fn lt(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, Runti
     }
 }
 
-pub fn lt_eq(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn lt_eq(left: RuntimeValue, right: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
         Ok(RuntimeValue::Bool(l <= r))
     }
@@ -100,16 +135,17 @@ pub fn lt_eq(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, Ru
     }
 }
 
-pub fn not_eq(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
-    if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
-        Ok(RuntimeValue::Bool(l != r))
-    }
-    else {
-        Err(RuntimeError::InvalidOperation)
+pub fn not_eq(left: RuntimeValue, right: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
+    match (left, right) {
+        (RuntimeValue::Number(l), RuntimeValue::Number(r)) => Ok(RuntimeValue::Bool(l != r)),
+        (RuntimeValue::Bool(l), RuntimeValue::Bool(r)) => Ok(RuntimeValue::Bool(l != r)),
+        (RuntimeValue::List(l), RuntimeValue::List(r)) => Ok(RuntimeValue::Bool(l != r)),
+
+        _ => Err(RuntimeError::InvalidOperation)
     }
 }
 
-pub fn and(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn and(left: RuntimeValue, right: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let (RuntimeValue::Bool(l), RuntimeValue::Bool(r)) = (left, right) {
         Ok(RuntimeValue::Bool(l && r))
     }
@@ -118,7 +154,7 @@ pub fn and(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, Runt
     }
 }
 
-pub fn or(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn or(left: RuntimeValue, right: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let (RuntimeValue::Bool(l), RuntimeValue::Bool(r)) = (left, right) {
         Ok(RuntimeValue::Bool(l || r))
     }
@@ -127,20 +163,99 @@ pub fn or(left: RuntimeValue, right: RuntimeValue) -> Result<RuntimeValue, Runti
     }
 }
 
-pub fn negate(value: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn bit_and(left: RuntimeValue, right: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
+    if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
+        Ok(RuntimeValue::Number(l & r))
+    }
+    else {
+        Err(RuntimeError::InvalidOperation)
+    }
+}
+
+pub fn bit_or(left: RuntimeValue, right: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
+    if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
+        Ok(RuntimeValue::Number(l | r))
+    }
+    else {
+        Err(RuntimeError::InvalidOperation)
+    }
+}
+
+pub fn bit_xor(left: RuntimeValue, right: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
+    if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
+        Ok(RuntimeValue::Number(l ^ r))
+    }
+    else {
+        Err(RuntimeError::InvalidOperation)
+    }
+}
+
+// Shift amounts must be smaller than the configured integer width
+fn shift_amount(amount: i64, options: &CompilerOptions) -> Result<u32, RuntimeError> {
+    u32::try_from(amount)
+        .ok()
+        .filter(|amount| *amount < options.int_width.bits())
+        .ok_or(RuntimeError::InvalidShift { amount, bits: options.int_width.bits() })
+}
+
+pub fn shift_left(left: RuntimeValue, right: RuntimeValue, options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
+    if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
+        let amount = shift_amount(r, options)?;
+        options.fit_integer((l as i128) << amount).map(RuntimeValue::Number).ok_or(RuntimeError::IntegerOverflow)
+    }
+    else {
+        Err(RuntimeError::InvalidOperation)
+    }
+}
+
+pub fn shift_right(left: RuntimeValue, right: RuntimeValue, options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
+    if let (RuntimeValue::Number(l), RuntimeValue::Number(r)) = (left, right) {
+        let amount = shift_amount(r, options)?;
+        Ok(RuntimeValue::Number(l >> amount))
+    }
+    else {
+        Err(RuntimeError::InvalidOperation)
+    }
+}
+
+pub fn negate(value: RuntimeValue, options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let RuntimeValue::Number(value) = value {
-        Ok(RuntimeValue::Number(- value))
+        options.fit_integer(-(value as i128)).map(RuntimeValue::Number).ok_or(RuntimeError::IntegerOverflow)
     }
     else {
         Err(RuntimeError::InvalidOperation)
     }
 }
 
-pub fn not(value: RuntimeValue) -> Result<RuntimeValue, RuntimeError> {
+pub fn not(value: RuntimeValue, _options: &CompilerOptions) -> Result<RuntimeValue, RuntimeError> {
     if let RuntimeValue::Bool(value) = value {
         Ok(RuntimeValue::Bool(!value))
     }
     else {
         Err(RuntimeError::InvalidOperation)
     }
-}
\ No newline at end of file
+}
+
+pub fn len(arguments: Vec<RuntimeValue>) -> Result<RuntimeValue, RuntimeError> {
+    match arguments.as_slice() {
+        [RuntimeValue::List(elements)] => Ok(RuntimeValue::Number(elements.len() as i64)),
+        _ => Err(RuntimeError::InvalidOperation)
+    }
+}
+
+pub fn clamp(arguments: Vec<RuntimeValue>) -> Result<RuntimeValue, RuntimeError> {
+    match arguments.as_slice() {
+        [RuntimeValue::Number(_), RuntimeValue::Number(lo), RuntimeValue::Number(hi)] if lo > hi =>
+            Err(RuntimeError::InvalidClampRange { lo: *lo, hi: *hi }),
+        [RuntimeValue::Number(value), RuntimeValue::Number(lo), RuntimeValue::Number(hi)] =>
+            Ok(RuntimeValue::Number((*value).clamp(*lo, *hi))),
+        _ => Err(RuntimeError::InvalidOperation)
+    }
+}
+
+pub fn sign(arguments: Vec<RuntimeValue>) -> Result<RuntimeValue, RuntimeError> {
+    match arguments.as_slice() {
+        [RuntimeValue::Number(value)] => Ok(RuntimeValue::Number(value.signum())),
+        _ => Err(RuntimeError::InvalidOperation)
+    }
+}
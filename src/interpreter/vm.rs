@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+
+use crate::{ast::expression::{BinaryOperator, UnaryOperator}, bytecode::{Bytecode, Instruction}, compiler::CompilerOptions};
+
+use super::{RuntimeError, RuntimeFunctionsDispatcher, RuntimeValue};
+
+struct Frame {
+    // Scopes of the blocks entered in the frame, the innermost last
+    scopes: Vec<BTreeMap<String, RuntimeValue>>,
+    // Index of the instruction to resume at once the frame returns, None for the program itself
+    return_address: Option<usize>,
+}
+
+impl Frame {
+    fn new(return_address: Option<usize>) -> Self {
+        Frame { scopes: vec![BTreeMap::new()], return_address }
+    }
+}
+
+// Executes bytecode with an operand stack, sharing operators and native functions with the tree-walking interpreter
+pub struct Vm {
+    stack: Vec<RuntimeValue>,
+    frames: Vec<Frame>,
+    dispatcher: RuntimeFunctionsDispatcher,
+    options: CompilerOptions,
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Self::with_options(CompilerOptions::default())
+    }
+
+    pub fn with_options(options: CompilerOptions) -> Self {
+        Vm {
+            stack: Vec::new(),
+            frames: vec![Frame::new(None)],
+            dispatcher: RuntimeFunctionsDispatcher::new(),
+            options,
+        }
+    }
+
+    // Errors are located at the span of the statement or expression whose instruction failed
+    pub fn run(&mut self, program: &Bytecode) -> Result<(), RuntimeError> {
+        let mut pc = 0;
+
+        while let Some(instruction) = program.instructions.get(pc) {
+            let index = pc;
+            pc += 1;
+
+            self.execute(instruction, program, &mut pc).map_err(|error| match program.spans.get(index) {
+                Some(span) => error.at(span.clone()),
+                None => error,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self, instruction: &Instruction, program: &Bytecode, pc: &mut usize) -> Result<(), RuntimeError> {
+        match instruction {
+            Instruction::PushInt(index) => {
+                let value = *program.constants.get(*index).ok_or(RuntimeError::InvalidOperation)?;
+                self.stack.push(RuntimeValue::Number(value));
+            }
+            Instruction::PushBool(value) => self.stack.push(RuntimeValue::Bool(*value)),
+            Instruction::PushUnit => self.stack.push(RuntimeValue::Unit),
+            Instruction::Pop => {
+                self.pop()?;
+            }
+            Instruction::Negate => self.unary(UnaryOperator::Negate)?,
+            Instruction::Not => self.unary(UnaryOperator::Not)?,
+            Instruction::LoadVar(name) => {
+                let value = self.load_variable(name)?;
+                self.stack.push(value);
+            }
+            Instruction::DeclareVar(name) => {
+                let value = self.pop()?;
+                self.current_scope().insert(name.clone(), value);
+            }
+            Instruction::StoreVar(name) => {
+                let value = self.pop()?;
+                *self.variable_mut(name)? = value;
+            }
+            Instruction::EnterScope => self.current_frame().scopes.push(BTreeMap::new()),
+            Instruction::ExitScope => {
+                self.current_frame().scopes.pop();
+            }
+            Instruction::Jump(target) => *pc = *target,
+            Instruction::JumpIfFalse(target) => match self.pop()? {
+                RuntimeValue::Bool(false) => *pc = *target,
+                RuntimeValue::Bool(true) => {}
+                _ => return Err(RuntimeError::InvalidOperation),
+            },
+            Instruction::Call { name, arg_count } => {
+                let arguments = self.stack.split_off(self.stack.len().checked_sub(*arg_count).ok_or(RuntimeError::InvalidOperation)?);
+
+                if let Some(function) = program.functions.get(name) {
+                    if arguments.len() != function.parameters.len() {
+                        return Err(RuntimeError::ArgumentCountMismatch { expected: function.parameters.len(), found: arguments.len() });
+                    }
+
+                    let mut frame = Frame::new(Some(*pc));
+                    frame.scopes[0].extend(function.parameters.iter().cloned().zip(arguments));
+                    self.frames.push(frame);
+                    *pc = function.address;
+                } else {
+                    let native_function = *self.dispatcher
+                        .get_native_function(name)
+                        .ok_or_else(|| RuntimeError::FunctionNotFound(name.clone()))?;
+                    self.stack.push(native_function(arguments)?);
+                }
+            }
+            // The returned value stays on top of the stack for the caller
+            Instruction::Return => {
+                // The program's own frame stays, so its variables can be inspected after the run
+                if self.frames.len() == 1 {
+                    *pc = program.instructions.len();
+                    return Ok(());
+                }
+                let frame = self.frames.pop().ok_or(RuntimeError::InvalidOperation)?;
+                *pc = frame.return_address.ok_or(RuntimeError::InvalidOperation)?;
+            }
+            binary => {
+                let operator = binary_operator(binary).ok_or(RuntimeError::InvalidOperation)?;
+                self.binary(operator)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn globals(&self) -> &BTreeMap<String, RuntimeValue> {
+        &self.frames[0].scopes[0]
+    }
+
+    fn current_frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("the program frame is never popped")
+    }
+
+    fn current_scope(&mut self) -> &mut BTreeMap<String, RuntimeValue> {
+        self.current_frame().scopes.last_mut().expect("a frame keeps its outermost scope")
+    }
+
+    fn pop(&mut self) -> Result<RuntimeValue, RuntimeError> {
+        self.stack.pop().ok_or(RuntimeError::InvalidOperation)
+    }
+
+    // Finds the frame and scope of a variable: the scopes of the current frame, innermost first, then the program's globals
+    fn locate_variable(&self, name: &str) -> Result<(usize, usize), RuntimeError> {
+        let current = self.frames.len() - 1;
+        self.frames[current].scopes
+            .iter()
+            .rposition(|scope| scope.contains_key(name))
+            .map(|scope| (current, scope))
+            .or_else(|| self.frames[0].scopes[0].contains_key(name).then_some((0, 0)))
+            .ok_or_else(|| RuntimeError::VariableNotFound(name.to_string()))
+    }
+
+    fn load_variable(&self, name: &str) -> Result<RuntimeValue, RuntimeError> {
+        let (frame, scope) = self.locate_variable(name)?;
+        Ok(self.frames[frame].scopes[scope][name].clone())
+    }
+
+    fn variable_mut(&mut self, name: &str) -> Result<&mut RuntimeValue, RuntimeError> {
+        let (frame, scope) = self.locate_variable(name)?;
+        Ok(self.frames[frame].scopes[scope].get_mut(name).expect("the variable was just located"))
+    }
+
+    fn binary(&mut self, operator: BinaryOperator) -> Result<(), RuntimeError> {
+        let right = self.pop()?;
+        let left = self.pop()?;
+        let function = self.dispatcher.get_binary_operator_function(&operator).ok_or(RuntimeError::InvalidOperation)?;
+        let result = function(left, right, &self.options)?;
+        self.stack.push(result);
+        Ok(())
+    }
+
+    fn unary(&mut self, operator: UnaryOperator) -> Result<(), RuntimeError> {
+        let operand = self.pop()?;
+        let function = self.dispatcher.get_unary_operator_function(&operator).ok_or(RuntimeError::InvalidOperation)?;
+        let result = function(operand, &self.options)?;
+        self.stack.push(result);
+        Ok(())
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn binary_operator(instruction: &Instruction) -> Option<BinaryOperator> {
+    let operator = match instruction {
+        Instruction::Add => BinaryOperator::Add,
+        Instruction::Sub => BinaryOperator::Subtract,
+        Instruction::Mul => BinaryOperator::Multiply,
+        Instruction::Div => BinaryOperator::Divide,
+        Instruction::Mod => BinaryOperator::Modulus,
+        Instruction::Pow => BinaryOperator::Power,
+        Instruction::Equal => BinaryOperator::Equal,
+        Instruction::NotEqual => BinaryOperator::NotEqual,
+        Instruction::LessThan => BinaryOperator::LessThan,
+        Instruction::GreaterThan => BinaryOperator::GreaterThan,
+        Instruction::LessThanOrEqual => BinaryOperator::LessThanOrEqual,
+        Instruction::GreaterThanOrEqual => BinaryOperator::GreaterThanOrEqual,
+        Instruction::BitAnd => BinaryOperator::BitAnd,
+        Instruction::BitOr => BinaryOperator::BitOr,
+        Instruction::BitXor => BinaryOperator::BitXor,
+        Instruction::ShiftLeft => BinaryOperator::ShiftLeft,
+        Instruction::ShiftRight => BinaryOperator::ShiftRight,
+        Instruction::And => BinaryOperator::And,
+        Instruction::Or => BinaryOperator::Or,
+        _ => return None,
+    };
+    Some(operator)
+}
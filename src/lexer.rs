@@ -1,4 +1,6 @@
-use std::fmt;
+use std::{fmt, str::FromStr};
+
+use crate::diagnostic::{Diagnostic, Diagnostics};
 
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum TokenKind {
@@ -7,35 +9,56 @@ pub enum TokenKind {
 
     // Keywords
     LetKeyword,
+    ConstKeyword,
     BeKeyword,
     AndKeyword,
     OrKeyword,
+    BandKeyword,
+    BorKeyword,
+    BxorKeyword,
+    ShlKeyword,
+    ShrKeyword,
     NotKeyword,
     SetKeyword,
+    SwapKeyword,
+    IncreaseKeyword,
+    DecreaseKeyword,
+    ByKeyword,
     ToKeyword,
     IfKeyword,
     ThenKeyword,
     EndKeyword,
     ElseKeyword,
+    ElifKeyword,
     TrueKeyword,
     FalseKeyword,
+    IntKeyword,
+    BoolKeyword,
     WhileKeyword,
     DoKeyword,
     ForKeyword,
     FromKeyword,
     StepKeyword,
+    RepeatKeyword,
+    TimesKeyword,
+    WhenKeyword,
+    IsKeyword,
+    OtherwiseKeyword,
     DefineKeyword,
     FunctionKeyword,
     WithKeyword,
     AsKeyword,
+    ReturnsKeyword,
     ReturnKeyword,
 
     // Operators
     Plus,
     Minus,
     Star,
+    StarStar,
     Slash,
     Percent,
+    Equal,
     EqualEqual,
     NotEqual,
     LessThan,
@@ -46,7 +69,11 @@ pub enum TokenKind {
     
     LeftParen,
     RightParen,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Semicolon,
+    Colon,
 
     Identifier,
 
@@ -60,13 +87,21 @@ impl fmt::Display for TokenKind {
         let s = match self {
             TokenKind::Number => "Number",
             TokenKind::LetKeyword => "let",
+            TokenKind::ConstKeyword => "const",
             TokenKind::BeKeyword => "be",
             TokenKind::AndKeyword => "and",
             TokenKind::OrKeyword => "or",
+            TokenKind::BandKeyword => "band",
+            TokenKind::BorKeyword => "bor",
+            TokenKind::BxorKeyword => "bxor",
+            TokenKind::ShlKeyword => "shl",
+            TokenKind::ShrKeyword => "shr",
             TokenKind::Plus => "+",
             TokenKind::Minus => "-",
             TokenKind::Star => "*",
+            TokenKind::StarStar => "**",
             TokenKind::Slash => "/",
+            TokenKind::Equal => "=",
             TokenKind::EqualEqual => "==",
             TokenKind::NotEqual => "!=",
             TokenKind::LessThan => "<",
@@ -75,43 +110,141 @@ impl fmt::Display for TokenKind {
             TokenKind::GreaterThanOrEqual => ">=",
             TokenKind::LeftParen => "(",
             TokenKind::RightParen => ")",
+            TokenKind::LeftBracket => "[",
+            TokenKind::RightBracket => "]",
             TokenKind::Identifier => "Identifier",
             TokenKind::Unknown => "Unknown",
             TokenKind::EndOfFile => "EndOfFile",
             TokenKind::NotKeyword => "not",
             TokenKind::Bang => "!",
             TokenKind::SetKeyword => "set",
+            TokenKind::SwapKeyword => "swap",
+            TokenKind::IncreaseKeyword => "increase",
+            TokenKind::DecreaseKeyword => "decrease",
+            TokenKind::ByKeyword => "by",
             TokenKind::ToKeyword => "to",
             TokenKind::IfKeyword => "if",
             TokenKind::ThenKeyword => "then",
             TokenKind::EndKeyword => "end",
             TokenKind::ElseKeyword => "else",
+            TokenKind::ElifKeyword => "elif",
             TokenKind::TrueKeyword => "true",
             TokenKind::FalseKeyword => "false",
+            TokenKind::IntKeyword => "int",
+            TokenKind::BoolKeyword => "bool",
             TokenKind::WhileKeyword => "while",
             TokenKind::DoKeyword => "do",
             TokenKind::Percent => "%",
             TokenKind::ForKeyword => "for",
             TokenKind::FromKeyword => "from",
             TokenKind::StepKeyword => "step",
+            TokenKind::RepeatKeyword => "repeat",
+            TokenKind::TimesKeyword => "times",
+            TokenKind::WhenKeyword => "when",
+            TokenKind::IsKeyword => "is",
+            TokenKind::OtherwiseKeyword => "otherwise",
             TokenKind::DefineKeyword => "define",
             TokenKind::FunctionKeyword => "function",
             TokenKind::WithKeyword => "with",
             TokenKind::AsKeyword => "as",
+            TokenKind::ReturnsKeyword => "returns",
             TokenKind::Comma => ",",
+            TokenKind::Semicolon => ";",
+            TokenKind::Colon => ":",
             TokenKind::ReturnKeyword => "return",
         };
         write!(f, "{s}")
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl FromStr for TokenKind {
+    type Err = String;
+
+    // Inverse of the Display impl
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind = match s {
+            "Number" => TokenKind::Number,
+            "let" => TokenKind::LetKeyword,
+            "const" => TokenKind::ConstKeyword,
+            "be" => TokenKind::BeKeyword,
+            "and" => TokenKind::AndKeyword,
+            "or" => TokenKind::OrKeyword,
+            "band" => TokenKind::BandKeyword,
+            "bor" => TokenKind::BorKeyword,
+            "bxor" => TokenKind::BxorKeyword,
+            "shl" => TokenKind::ShlKeyword,
+            "shr" => TokenKind::ShrKeyword,
+            "+" => TokenKind::Plus,
+            "-" => TokenKind::Minus,
+            "*" => TokenKind::Star,
+            "**" => TokenKind::StarStar,
+            "/" => TokenKind::Slash,
+            "=" => TokenKind::Equal,
+            "==" => TokenKind::EqualEqual,
+            "!=" => TokenKind::NotEqual,
+            "<" => TokenKind::LessThan,
+            ">" => TokenKind::GreaterThan,
+            "<=" => TokenKind::LessThanOrEqual,
+            ">=" => TokenKind::GreaterThanOrEqual,
+            "(" => TokenKind::LeftParen,
+            ")" => TokenKind::RightParen,
+            "[" => TokenKind::LeftBracket,
+            "]" => TokenKind::RightBracket,
+            "Identifier" => TokenKind::Identifier,
+            "Unknown" => TokenKind::Unknown,
+            "EndOfFile" => TokenKind::EndOfFile,
+            "not" => TokenKind::NotKeyword,
+            "!" => TokenKind::Bang,
+            "set" => TokenKind::SetKeyword,
+            "swap" => TokenKind::SwapKeyword,
+            "increase" => TokenKind::IncreaseKeyword,
+            "decrease" => TokenKind::DecreaseKeyword,
+            "by" => TokenKind::ByKeyword,
+            "to" => TokenKind::ToKeyword,
+            "if" => TokenKind::IfKeyword,
+            "then" => TokenKind::ThenKeyword,
+            "end" => TokenKind::EndKeyword,
+            "else" => TokenKind::ElseKeyword,
+            "elif" => TokenKind::ElifKeyword,
+            "true" => TokenKind::TrueKeyword,
+            "false" => TokenKind::FalseKeyword,
+            "int" => TokenKind::IntKeyword,
+            "bool" => TokenKind::BoolKeyword,
+            "while" => TokenKind::WhileKeyword,
+            "do" => TokenKind::DoKeyword,
+            "%" => TokenKind::Percent,
+            "for" => TokenKind::ForKeyword,
+            "from" => TokenKind::FromKeyword,
+            "step" => TokenKind::StepKeyword,
+            "repeat" => TokenKind::RepeatKeyword,
+            "times" => TokenKind::TimesKeyword,
+            "when" => TokenKind::WhenKeyword,
+            "is" => TokenKind::IsKeyword,
+            "otherwise" => TokenKind::OtherwiseKeyword,
+            "define" => TokenKind::DefineKeyword,
+            "function" => TokenKind::FunctionKeyword,
+            "with" => TokenKind::WithKeyword,
+            "as" => TokenKind::AsKeyword,
+            "returns" => TokenKind::ReturnsKeyword,
+            "," => TokenKind::Comma,
+            ";" => TokenKind::Semicolon,
+            ":" => TokenKind::Colon,
+            "return" => TokenKind::ReturnKeyword,
+            _ => return Err(format!("Unknown token kind '{}'", s)),
+        };
+        Ok(kind)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TokenPosition {
     pub line: usize,
     pub column: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+// `end` is exclusive: `define` at column 1 spans columns 1 to 6 and ends at column 7.
+// A token never spans a newline, so its end is on the line it starts on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TextSpan {
     pub start: TokenPosition,
     pub end: TokenPosition,
@@ -120,10 +253,14 @@ pub struct TextSpan {
 impl TextSpan {
     pub fn union(&self, other: &Self) -> Self {
         TextSpan {
-            start: TokenPosition { line: self.start.line.min(other.start.line), column: self.start.column.min(other.start.column) },
-            end: TokenPosition { line: self.end.line.max(other.end.line), column: self.end.column.max(other.end.column) },
+            start: self.start.clone().min(other.start.clone()),
+            end: self.end.clone().max(other.end.clone()),
         }
     }
+
+    pub fn contains(&self, position: &TokenPosition) -> bool {
+        self.start <= *position && *position < self.end
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -139,18 +276,82 @@ impl Token {
             start: self.position.clone(),
             end: TokenPosition {
                 line: self.position.line,
-                column: self.position.column + self.value.len(),
+                // Columns count characters, so a run of unknown multi-byte characters is covered exactly
+                column: self.position.column + self.value.chars().count(),
             },
         }
     }
 }
 
+// Serializes tokens one per line as 'line:column kind value', so a token stream can be replayed into the parser
+pub fn tokens_to_string(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .map(|token| format!("{}:{} {} {}\n", token.position.line, token.position.column, token.kind, token.value))
+        .collect()
+}
+
+// A line of a token capture that cannot be read back, numbered from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenReplayError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TokenReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// Reads back the output of `tokens_to_string`, failing on the first malformed line
+pub fn tokens_from_string(serialized: &str) -> Result<Vec<Token>, TokenReplayError> {
+    serialized
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            parse_token_line(line).map_err(|message| TokenReplayError { line: index + 1, message })
+        })
+        .collect()
+}
+
+fn parse_token_line(line: &str) -> Result<Token, String> {
+    let mut parts = line.splitn(3, ' ');
+    let position = parts.next().unwrap_or_default();
+    let (line_number, column) = position
+        .split_once(':')
+        .ok_or_else(|| format!("Missing token position in '{}'", line))?;
+    let kind = parts.next().ok_or_else(|| format!("Missing token kind in '{}'", line))?;
+    let value = parts.next().unwrap_or_default();
+
+    Ok(Token {
+        kind: kind.parse()?,
+        value: value.to_string(),
+        position: TokenPosition {
+            line: line_number.parse().map_err(|_| format!("Invalid line number '{}'", line_number))?,
+            column: column.parse().map_err(|_| format!("Invalid column '{}'", column))?,
+        },
+    })
+}
+
+
+// Identifiers start with a Unicode letter or '_' and continue with Unicode letters, digits or '_', e.g. `café`.
+// Numbers only use the ASCII digits 0-9.
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_identifier_continue(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
 
 static OPERATORS: &[(&str, TokenKind)] = &[
     ("+", TokenKind::Plus), 
     ("-", TokenKind::Minus), 
     ("*", TokenKind::Star), 
+    ("**", TokenKind::StarStar),
     ("/", TokenKind::Slash),
+    ("=", TokenKind::Equal),
     ("==", TokenKind::EqualEqual),
     ("!=", TokenKind::NotEqual),
     ("<", TokenKind::LessThan),
@@ -177,6 +378,23 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // Lexes the whole input up front, reporting each run of unknown characters instead of returning it as a token.
+    // The end of file token is kept, so the tokens can still be handed to the parser.
+    pub fn lex_with_diagnostics(input: &str) -> (Vec<Token>, Diagnostics) {
+        let mut diagnostics = Diagnostics::new();
+        let tokens = Lexer::new(input)
+            .filter(|token| {
+                if token.kind == TokenKind::Unknown {
+                    diagnostics.report(Diagnostic::unknown_characters(token.clone()));
+                    return false;
+                }
+                true
+            })
+            .collect();
+
+        (tokens, diagnostics)
+    }
+
     pub fn next_token(&mut self) -> Option<Token> {
         self.handle_whitespaces();
         let next_char_opt = self.peek();
@@ -187,10 +405,10 @@ impl<'a> Lexer<'a> {
         if next_char.is_ascii_digit() {
             return Some(self.number_token());
         }
-        return self.operator_token()
+        self.operator_token()
             .or_else(|| self.symbol_token())
             .or_else(|| self.identifier_token())
-            .or_else(|| self.unknown_token());
+            .or_else(|| self.unknown_token())
     }
 
     fn advance(&mut self) -> char {
@@ -204,28 +422,18 @@ impl<'a> Lexer<'a> {
         c
     }
 
-    fn unadvance(&mut self, count: usize) {
-
-        if count == 0 {
-            return; // No need to rewind if count is zero
-        }
-        // Rewind the input buffer
-        self.input.rewind(count);
-
-        // Recompute line and column by scanning from the start up to the current position
-        let mut line = 1;
-        let mut col = 1;
-        for c in self.input.start().chars() {
-            if c == '\n' {
-            line += 1;
-            col = 1;
-            } else {
-            col += 1;
-            }
+    // Gives back the characters consumed past the token start, keeping only the first `kept` ones.
+    // The position is recomputed forward from the token start over the kept characters.
+    fn backtrack(&mut self, start_pos: TokenPosition, consumed: usize, kept: usize) {
+        if consumed == kept {
+            return;
         }
-        self.position.line = line;
-        self.position.column = col;
+        self.input.rewind(consumed);
+        self.position = start_pos;
+        for _ in 0..kept {
+            self.advance();
         }
+    }
 
     fn peek(&mut self) -> Option<char> {
         self.input.peek()
@@ -237,10 +445,11 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // Underscores are kept in the value, e.g. `1_000`, and checked by the parser
     fn number_token(&mut self) -> Token {
         let mut number = String::new();
         let start_pos = self.position.clone();
-        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '_') {
             number.push(self.advance());
         }
         Token {
@@ -255,12 +464,14 @@ impl<'a> Lexer<'a> {
         let mut op = String::new();
         let mut last_valid_kind = None;
         let mut last_valid_len = 0;
+        let mut last_valid_chars = 0;
         let mut chars_consumed = 0;
         while let Some(c) = self.peek() {
             op.push(c);
             if let Some(kind) = self.match_operator(&op) {
                 last_valid_kind = Some(kind);
                 last_valid_len = op.len();
+                last_valid_chars = chars_consumed + 1;
             }
             if OPERATORS.iter().any(|(s, _)| s.starts_with(&op)) {
                 self.advance();
@@ -271,9 +482,7 @@ impl<'a> Lexer<'a> {
             }
         }
         if let Some(kind) = last_valid_kind {
-            if last_valid_len < chars_consumed {
-                self.unadvance(chars_consumed - last_valid_len);
-            }
+            self.backtrack(start_pos.clone(), chars_consumed, last_valid_chars);
             let value = op[..last_valid_len].to_string();
             Some(Token {
                 kind,
@@ -282,7 +491,7 @@ impl<'a> Lexer<'a> {
             })
         } 
         else {
-            self.unadvance(chars_consumed);
+            self.backtrack(start_pos, chars_consumed, 0);
             None
         }
     }
@@ -310,12 +519,12 @@ impl<'a> Lexer<'a> {
     fn identifier_token(&mut self) -> Option<Token> {
         let mut identifier = String::new();
         let start_pos = self.position.clone();
-        if self.peek().is_some_and(|c| c.is_alphabetic() || c == '_') {
+        if self.peek().is_some_and(is_identifier_start) {
             identifier.push(self.advance());
         } else {
             return None;
         }
-        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+        while self.peek().is_some_and(is_identifier_continue) {
             identifier.push(self.advance());
         }
         Some(Token {
@@ -347,6 +556,7 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // An empty token placed just past the last character, so its span has no width
     fn end_of_file_token(&mut self) -> Option<Token> {
 
         if self.is_eof_encountered {
@@ -356,17 +566,21 @@ impl<'a> Lexer<'a> {
         self.is_eof_encountered = true;
         Some(Token {
             kind: TokenKind::EndOfFile,
-            value: "EOF".to_string(),
+            value: String::new(),
             position: self.position.clone(),
         })
     }
 
+    // A known character can start a token, everything else is grouped into an Unknown token
     fn is_char_known(&self, c: char) -> bool {
+        let mut buffer = [0; 4];
+        let c_str = &*c.encode_utf8(&mut buffer);
+
         c.is_ascii_digit()
-            || c.is_alphabetic()
+            || is_identifier_start(c)
             || c.is_whitespace()
             || self.match_symbol(c).is_some()
-            || OPERATORS.iter().any(|(op_str, _)| **op_str == c.to_string())
+            || OPERATORS.iter().any(|(op_str, _)| *op_str == c_str)
     }
 
     // Helper to match symbol
@@ -374,7 +588,11 @@ impl<'a> Lexer<'a> {
         match c {
             '(' => Some(TokenKind::LeftParen),
             ')' => Some(TokenKind::RightParen),
+            '[' => Some(TokenKind::LeftBracket),
+            ']' => Some(TokenKind::RightBracket),
             ',' => Some(TokenKind::Comma),
+            ';' => Some(TokenKind::Semicolon),
+            ':' => Some(TokenKind::Colon),
             _ => None,
         }
     }
@@ -383,27 +601,46 @@ impl<'a> Lexer<'a> {
     fn match_identifier_or_keyword(&self, identifier: &str) -> TokenKind {
         match identifier {
             "let" => TokenKind::LetKeyword,
+            "const" => TokenKind::ConstKeyword,
             "be" => TokenKind::BeKeyword,
             "and" => TokenKind::AndKeyword,
             "or" => TokenKind::OrKeyword,
+            "band" => TokenKind::BandKeyword,
+            "bor" => TokenKind::BorKeyword,
+            "bxor" => TokenKind::BxorKeyword,
+            "shl" => TokenKind::ShlKeyword,
+            "shr" => TokenKind::ShrKeyword,
             "not" => TokenKind::NotKeyword,
             "set" => TokenKind::SetKeyword,
+            "swap" => TokenKind::SwapKeyword,
+            "increase" => TokenKind::IncreaseKeyword,
+            "decrease" => TokenKind::DecreaseKeyword,
+            "by" => TokenKind::ByKeyword,
             "to" => TokenKind::ToKeyword,
             "if" => TokenKind::IfKeyword,
             "then" => TokenKind::ThenKeyword,
             "end" => TokenKind::EndKeyword,
             "else" => TokenKind::ElseKeyword,
+            "elif" => TokenKind::ElifKeyword,
             "true" => TokenKind::TrueKeyword,
             "false" => TokenKind::FalseKeyword,
+            "int" => TokenKind::IntKeyword,
+            "bool" => TokenKind::BoolKeyword,
             "while" => TokenKind::WhileKeyword,
             "do" => TokenKind::DoKeyword,
             "for" => TokenKind::ForKeyword,
             "from" => TokenKind::FromKeyword,
             "step" => TokenKind::StepKeyword,
+            "repeat" => TokenKind::RepeatKeyword,
+            "times" => TokenKind::TimesKeyword,
+            "when" => TokenKind::WhenKeyword,
+            "is" => TokenKind::IsKeyword,
+            "otherwise" => TokenKind::OtherwiseKeyword,
             "define" => TokenKind::DefineKeyword,
             "function" => TokenKind::FunctionKeyword,
             "with" => TokenKind::WithKeyword,
             "as" => TokenKind::AsKeyword,
+            "returns" => TokenKind::ReturnsKeyword,
             "return" => TokenKind::ReturnKeyword,
             _ => TokenKind::Identifier,
         }
@@ -425,7 +662,7 @@ struct LexerInputBuffer<'a> {
 
 
 impl LexerInputBuffer<'_> {
-    fn new(input: &str) -> LexerInputBuffer {
+    fn new(input: &str) -> LexerInputBuffer<'_> {
         LexerInputBuffer {
             input,
             position: 0,
@@ -455,8 +692,4 @@ impl LexerInputBuffer<'_> {
             self.position = chars.as_str().len();
         }
     }
-
-    fn start(&self) -> &str {
-        &self.input[self.position..]
-    }
 }
\ No newline at end of file
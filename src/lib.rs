@@ -8,6 +8,10 @@ pub mod interpreter;
 pub mod symbols_table;
 pub mod resolver;
 pub mod types;
+pub mod serialize;
+pub mod formatter;
+pub mod bytecode;
+pub mod call_graph;
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -15,6 +19,8 @@ pub enum BlockType {
     IfBlock,
     WhileBlock,
     ForBlock,
+    RepeatBlock,
+    WhenBlock,
     ElseBlock,
     FunctionBlock,
 }
\ No newline at end of file
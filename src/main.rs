@@ -1,18 +1,38 @@
 use navacodelang::{ast::AstExplorer, compiler::{Compiler, SourceCode}, interpreter::Interpreter, utils::AstDebugPrinter};
 
 
+// Runs the program at the path given as first argument, `-` for stdin, or the bundled testing.nvc
 fn main() {
 
-    let source_code = SourceCode::from_file("testing.nvc").expect("Cannot read source file");
+    let path = std::env::args().nth(1).unwrap_or_else(|| "testing.nvc".to_string());
+    let source_code = if path == "-" {
+        SourceCode::from_reader(std::io::stdin())
+    } else {
+        SourceCode::from_file(&path)
+    };
+    let source_code = match source_code {
+        Ok(source_code) => source_code,
+        Err(e) => {
+            eprintln!("Cannot read source file '{}': {}", path, e);
+            std::process::exit(1);
+        }
+    };
     println!("Starting compilation...");
     let compiler = Compiler::new();
     let compilation_result = compiler.compile(&source_code);
     match compilation_result {
         Ok(compilation_unit) => {
             println!("Compilation successful!");
+            if compilation_unit.warnings.has_warnings() {
+                eprint!("{}", compilation_unit.warnings);
+            }
             AstDebugPrinter::new().explore_ast(&compilation_unit.ast);
             println!("Running code...");
-            Interpreter::interpret(&compilation_unit.ast);
+            let mut interpreter = Interpreter::with_options(*compiler.options());
+            if let Err(error) = interpreter.try_run_program(&compilation_unit.ast) {
+                eprintln!("Runtime error: {}", error);
+            }
+            interpreter.display_state();
         },
         Err(e) => {
             eprintln!("Compilation failed: {}", e);
@@ -1,6 +1,6 @@
 use std::iter::Peekable;
 
-use crate::{ast::{expression::{BinaryOperator, Expression, FunctionCallData, Literal, UnaryOperator}, statement::{IfThenBranch, Statement}, Ast}, diagnostic::{Diagnostic, Diagnostics}, lexer::{Token, TokenKind}, BlockType};
+use crate::{ast::{expression::{BinaryOperator, Expression, FunctionCallData, Literal, UnaryOperator}, statement::{IfThenBranch, Parameter, Statement}, Ast}, diagnostic::{Diagnostic, Diagnostics}, lexer::{TextSpan, Token, TokenKind}, types::Type, utils::edit_distance, BlockType};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ErrorRecoveryState {
@@ -11,20 +11,64 @@ enum ErrorRecoveryState {
 // Tokens that we can recover from
 static RECOVERY_END_POINTS: &[TokenKind] = &[
     TokenKind::LetKeyword,
+    TokenKind::ConstKeyword,
     TokenKind::SetKeyword,
+    TokenKind::SwapKeyword,
+    TokenKind::IncreaseKeyword,
+    TokenKind::DecreaseKeyword,
     TokenKind::IfKeyword,
     TokenKind::WhileKeyword,
     TokenKind::ForKeyword,
+    TokenKind::RepeatKeyword,
+    TokenKind::WhenKeyword,
     TokenKind::EndKeyword,
     TokenKind::ElseKeyword,
+    TokenKind::ElifKeyword,
+    TokenKind::IsKeyword,
+    TokenKind::OtherwiseKeyword,
     TokenKind::DefineKeyword
 ];
 
+// Keywords that can start a statement, checked against misspelled statement starts
+static STATEMENT_KEYWORDS: &[TokenKind] = &[
+    TokenKind::LetKeyword,
+    TokenKind::ConstKeyword,
+    TokenKind::SetKeyword,
+    TokenKind::SwapKeyword,
+    TokenKind::IncreaseKeyword,
+    TokenKind::DecreaseKeyword,
+    TokenKind::IfKeyword,
+    TokenKind::WhileKeyword,
+    TokenKind::ForKeyword,
+    TokenKind::RepeatKeyword,
+    TokenKind::WhenKeyword,
+    TokenKind::DefineKeyword,
+    TokenKind::ReturnKeyword,
+];
+
+// Tokens that can end an expression, where a malformed expression stops being skipped
+static EXPRESSION_RECOVERY_END_POINTS: &[TokenKind] = &[
+    TokenKind::ThenKeyword,
+    TokenKind::DoKeyword,
+    TokenKind::ToKeyword,
+    TokenKind::StepKeyword,
+    TokenKind::TimesKeyword,
+    TokenKind::IsKeyword,
+    TokenKind::ReturnKeyword,
+    TokenKind::Comma,
+    TokenKind::Semicolon,
+    TokenKind::RightParen,
+    TokenKind::RightBracket,
+];
+
 pub struct Parser<I: Iterator<Item = Token>> {
     tokens: Peekable<I>,
 
     recovery_states: Vec<ErrorRecoveryState>,
     consumed_tokens: Vec<TokenKind>,
+    // Line of the last consumed token, telling a token that continues it from one that starts a new statement
+    previous_line: usize,
+    diagnostics: Diagnostics,
 }
 
 impl<I: Iterator<Item = Token>> Parser<I> {
@@ -33,30 +77,37 @@ impl<I: Iterator<Item = Token>> Parser<I> {
             tokens: tokens.peekable(),
             recovery_states: Vec::new(),
             consumed_tokens: Vec::new(),
+            previous_line: 1,
+            diagnostics: Diagnostics::new(),
         }
     }
 
-    pub fn parse(mut self) -> Result<Ast, Diagnostics> {
-        let mut ast = Ast::new();
+    pub fn parse(self) -> Result<Ast, Diagnostics> {
+        let (ast, diagnostics) = self.parse_recovering();
 
-        let mut diagnostic = Diagnostics::new();
+        if diagnostics.has_errors() {
+            return Err(diagnostics);
+        }
+
+        Ok(ast)
+    }
+
+    // Returns the statements that parsed, even when errors were reported
+    pub fn parse_recovering(mut self) -> (Ast, Diagnostics) {
+        let mut ast = Ast::new();
 
         loop {
             match self.parse_statement() {
                 Ok(Some(stmt)) => ast.add_statement(stmt),
                 Ok(None) => break,
                 Err(diag) => {
-                    diagnostic.report(diag);
+                    self.diagnostics.report(diag);
                     self.recover();
                 }
             }
         }
 
-        if diagnostic.has_errors() {
-            return Err(diagnostic);
-        }
-
-        Ok(ast)
+        (ast, self.diagnostics)
     }
 
     fn push_recovery_state(&mut self, recovery_state: ErrorRecoveryState) {
@@ -74,6 +125,7 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     fn advance(&mut self) -> Token {
         let token = self.tokens.next().unwrap();
         self.consumed_tokens.push(token.kind);
+        self.previous_line = token.position.line;
         token
     }
 
@@ -100,13 +152,24 @@ impl<I: Iterator<Item = Token>> Parser<I> {
 
     }
 
+    // '=' is accepted in place of 'be'/'to' with a warning, since newcomers often write 'let x = 5'
+    fn expect_keyword_or_equal(&mut self, keyword: TokenKind) -> Result<Token, Diagnostic> {
+        if self.peek().kind == TokenKind::Equal {
+            let equal_token = self.advance();
+            self.diagnostics.report(Diagnostic::equal_instead_of_keyword(keyword, equal_token.span()));
+            return Ok(equal_token);
+        }
+
+        self.expect(&[keyword])
+    }
+
     fn recover(&mut self) {
 
         loop {
             let token_kind = self.peek().kind;
 
-            if token_kind == TokenKind::EndOfFile || RECOVERY_END_POINTS.contains(&token_kind) {
-                // If we reach the end of file or a statement start token, we can stop recovering
+            if token_kind == TokenKind::EndOfFile || token_kind == TokenKind::Semicolon || RECOVERY_END_POINTS.contains(&token_kind) {
+                // If we reach the end of file, a separator or a statement start token, we can stop recovering
                 break;
             }
             else {
@@ -118,7 +181,34 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     }
 
 
+    // Skips the rest of a malformed expression and returns the span of what was skipped
+    fn recover_expression(&mut self, start_span: TextSpan) -> TextSpan {
+        let mut span = start_span;
+
+        loop {
+            let token_kind = self.peek().kind;
+
+            if token_kind == TokenKind::EndOfFile 
+                || RECOVERY_END_POINTS.contains(&token_kind) 
+                || EXPRESSION_RECOVERY_END_POINTS.contains(&token_kind) {
+                break;
+            }
+
+            span = span.union(&self.advance().span());
+        }
+
+        span
+    }
+
+    // ';' separates statements explicitly, so any number of them is an empty statement
+    fn skip_semicolons(&mut self) {
+        while self.peek().kind == TokenKind::Semicolon {
+            self.advance();
+        }
+    }
+
     fn parse_statement(&mut self) -> Result<Option<Statement>, Diagnostic> {
+        self.skip_semicolons();
 
         let next_token_kind = self.peek().kind;
 
@@ -130,36 +220,51 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         match next_token_kind {
             TokenKind::LetKeyword => Ok(Some(self.parse_variable_declaration()?)),
 
+            TokenKind::ConstKeyword => Ok(Some(self.parse_const_declaration()?)),
+
             TokenKind::SetKeyword => Ok(Some(self.parse_variable_assignement()?)),
+
+            TokenKind::SwapKeyword => Ok(Some(self.parse_swap_statement()?)),
+
+            TokenKind::IncreaseKeyword | TokenKind::DecreaseKeyword => Ok(Some(self.parse_compound_assignment()?)),
             
             TokenKind::IfKeyword => 
-                Ok(Some(self.parse_if_statement().map_err(|diag| {
+                Ok(Some(self.parse_if_statement().inspect_err(|_| {
                     self.push_recovery_state(ErrorRecoveryState::RecoverFromBadBlock(BlockType::IfBlock));
-                    diag
                 })?)),
 
             TokenKind::WhileKeyword => 
-                Ok(Some(self.parse_while_statement().map_err(|diag| {
+                Ok(Some(self.parse_while_statement().inspect_err(|_| {
                     self.push_recovery_state(ErrorRecoveryState::RecoverFromBadBlock(BlockType::WhileBlock));
-                    diag
                 })?)),
 
             TokenKind::ForKeyword => 
-                Ok(Some(self.parse_for_statement().map_err(|diag| {
+                Ok(Some(self.parse_for_statement().inspect_err(|_| {
                     self.push_recovery_state(ErrorRecoveryState::RecoverFromBadBlock(BlockType::ForBlock));
-                    diag
+                })?)),
+
+            TokenKind::RepeatKeyword => 
+                Ok(Some(self.parse_repeat_statement().inspect_err(|_| {
+                    self.push_recovery_state(ErrorRecoveryState::RecoverFromBadBlock(BlockType::RepeatBlock));
+                })?)),
+
+            TokenKind::WhenKeyword => 
+                Ok(Some(self.parse_when_statement().inspect_err(|_| {
+                    self.push_recovery_state(ErrorRecoveryState::RecoverFromBadBlock(BlockType::WhenBlock));
                 })?)),
             
             TokenKind::DefineKeyword => 
-                Ok(Some(self.parse_function_definition().map_err(|diag| {
+                Ok(Some(self.parse_function_definition().inspect_err(|_| {
                     self.push_recovery_state(ErrorRecoveryState::RecoverFromBadBlock(BlockType::FunctionBlock));
-                    diag
                 })?)),
 
             TokenKind::Identifier =>
-                Ok(Some(self.parse_function_call().map(|data| Statement::FunctionCall(data))?)),
+                Ok(Some(self.parse_identifier_statement()?)),
 
             TokenKind::ReturnKeyword => Ok(Some(self.parse_return_statement()?)),
+
+            TokenKind::LeftParen | TokenKind::Number | TokenKind::TrueKeyword | TokenKind::FalseKeyword =>
+                Ok(Some(Statement::ExpressionStatement(self.parse_expression()?))),
             
             // Reporting errors
             TokenKind::ElseKeyword 
@@ -168,17 +273,39 @@ impl<I: Iterator<Item = Token>> Parser<I> {
                 self.parse_statement()
             }
 
-            TokenKind::ElseKeyword if self.consumed_tokens.last() == Some(&TokenKind::EndKeyword) => {
+            // An 'elif' after a bad 'if' starts the rest of the chain, which consumes the shared 'end'
+            TokenKind::ElifKeyword 
+                if self.current_recovery_state() == Some(&ErrorRecoveryState::RecoverFromBadBlock(BlockType::IfBlock)) => {
+                self.pop_recovery_state();
+                Ok(Some(self.parse_if_statement().inspect_err(|_| {
+                    self.push_recovery_state(ErrorRecoveryState::RecoverFromBadBlock(BlockType::IfBlock));
+                })?))
+            }
+
+            // The remaining arms of a bad 'when' are skipped up to their bodies, which still get parsed
+            TokenKind::IsKeyword | TokenKind::OtherwiseKeyword
+                if self.current_recovery_state() == Some(&ErrorRecoveryState::RecoverFromBadBlock(BlockType::WhenBlock)) => {
+                let arm_span = self.advance().span();
+                if next_token_kind == TokenKind::IsKeyword {
+                    self.recover_expression(arm_span);
+                    if self.peek().kind == TokenKind::ThenKeyword {
+                        self.advance();
+                    }
+                }
+                self.parse_statement()
+            }
+
+            TokenKind::ElseKeyword | TokenKind::ElifKeyword if self.consumed_tokens.last() == Some(&TokenKind::EndKeyword) => {
                self.push_recovery_state(ErrorRecoveryState::RecoverFromBadBlock(BlockType::ElseBlock));
                 Err(
-                    Diagnostic::unexpected_else_after_end(self.advance().span())
+                    Diagnostic::unexpected_else_after_end(next_token_kind, self.advance().span())
                 )
             }
 
-            TokenKind::ElseKeyword => {
+            TokenKind::ElseKeyword | TokenKind::ElifKeyword => {
                 self.push_recovery_state(ErrorRecoveryState::RecoverFromBadBlock(BlockType::ElseBlock));
                 Err(
-                    Diagnostic::unexpected_else_token(self.advance().span())
+                    Diagnostic::unexpected_else_token(next_token_kind, self.advance().span())
                 )
             }
 
@@ -194,18 +321,24 @@ impl<I: Iterator<Item = Token>> Parser<I> {
                 Diagnostic::unexpected_end_token(self.advance().span())
             ),
             _ => {
-                return Err(Diagnostic::unexpected_token(
+                Err(Diagnostic::unexpected_token(
                     RECOVERY_END_POINTS.to_vec(),
                     self.advance()
-                ));
+                ))
             }
         }
     }
 
     fn parse_statements_until(&mut self, stop_tokens: &[TokenKind]) -> Result<Statement, Diagnostic> {
         let mut statements = Vec::new();
+        let start_span = self.peek().span();
+
+        loop {
+            self.skip_semicolons();
+            if stop_tokens.contains(&self.peek().kind) {
+                break;
+            }
 
-        while !stop_tokens.contains(&self.peek().kind) {
             if let Some(stmt) = self.parse_statement()? {
                 statements.push(stmt);
             } else {
@@ -214,57 +347,134 @@ impl<I: Iterator<Item = Token>> Parser<I> {
             }
         }
         
-        Ok(Statement::BlockStatement { statements })
+        let span = start_span.union(&self.peek().span());
+        Ok(Statement::BlockStatement { statements, span })
     }
 
-    fn parse_tokens_list(&mut self, target_token_type: TokenKind, separator: Option<TokenKind>) -> Result<Vec<Token>, Diagnostic> {
-        let mut tokens = vec![self.expect(&[target_token_type])?];
+    fn parse_parameters(&mut self) -> Result<Vec<Parameter>, Diagnostic> {
+        let mut parameters = vec![self.parse_parameter()?];
 
-        loop {
-            if separator.is_some_and(|sep| self.peek().kind != sep) {
-                break;
-            }
+        while self.peek().kind == TokenKind::Comma {
+            self.advance(); // consume the comma
+            parameters.push(self.parse_parameter()?);
+        }
 
-            if separator.is_some() {
-                self.advance();
-            }
+        Ok(parameters)
+    }
 
-            tokens.push(self.expect(&[target_token_type])?);
-        }
+    fn parse_parameter(&mut self) -> Result<Parameter, Diagnostic> {
+        let name = self.expect(&[TokenKind::Identifier])?;
+        let type_annotation = self.parse_type_annotation()?;
 
-        Ok(tokens)
+        Ok(Parameter { name, type_annotation })
     }
 
     fn parse_variable_declaration(&mut self) -> Result<Statement, Diagnostic> {
-        self.expect(&[TokenKind::LetKeyword])?;
+        let keyword_span = self.expect(&[TokenKind::LetKeyword])?.span();
         let name_token = self.expect(&[TokenKind::Identifier])?;
-        self.expect(&[TokenKind::BeKeyword])?;
-        let value = self.parse_expression()?;
+        let type_annotation = self.parse_type_annotation()?;
+        self.expect_keyword_or_equal(TokenKind::BeKeyword)?;
+
+        // `let count be int` starts the variable at the zero value of the type
+        let value = if matches!(self.peek().kind, TokenKind::IntKeyword | TokenKind::BoolKeyword) {
+            let type_token = self.advance();
+            zero_value(&type_token)
+        } else {
+            self.parse_expression()?
+        };
 
         Ok(Statement::VariableDeclaration {
+            keyword_span,
+            name: name_token,
+            type_annotation,
+            value,
+        })
+    }
+
+    // An optional `: int` or `: bool` after a declared name
+    fn parse_type_annotation(&mut self) -> Result<Option<Type>, Diagnostic> {
+        if self.peek().kind != TokenKind::Colon {
+            return Ok(None);
+        }
+
+        self.advance();
+        let type_token = self.expect(&[TokenKind::IntKeyword, TokenKind::BoolKeyword])?;
+        Ok(Some(annotated_type(&type_token)))
+    }
+
+    fn parse_const_declaration(&mut self) -> Result<Statement, Diagnostic> {
+        let keyword_span = self.expect(&[TokenKind::ConstKeyword])?.span();
+        let name_token = self.expect(&[TokenKind::Identifier])?;
+        self.expect_keyword_or_equal(TokenKind::BeKeyword)?;
+        let value = self.parse_expression()?;
+
+        Ok(Statement::ConstDeclaration {
+            keyword_span,
             name: name_token,
             value,
         })
     }
 
     fn parse_variable_assignement(&mut self) -> Result<Statement, Diagnostic> {
-        self.expect(&[TokenKind::SetKeyword])?;
+        let keyword_span = self.expect(&[TokenKind::SetKeyword])?.span();
         let name_token = self.expect(&[TokenKind::Identifier])?;
-        self.expect(&[TokenKind::ToKeyword])?;
+        self.expect_keyword_or_equal(TokenKind::ToKeyword)?;
         let value = self.parse_expression()?;
 
         Ok(Statement::VariableAssignment {
+            keyword_span,
             name: name_token,
             value,
         })
     }
 
+    // `increase x by 5` is sugar for `set x to x + 5`, so later passes only ever see a plain assignment
+    fn parse_compound_assignment(&mut self) -> Result<Statement, Diagnostic> {
+        let keyword = self.expect(&[TokenKind::IncreaseKeyword, TokenKind::DecreaseKeyword])?;
+        let name_token = self.expect(&[TokenKind::Identifier])?;
+        self.expect(&[TokenKind::ByKeyword])?;
+        let amount = self.parse_expression()?;
+
+        let operator = match keyword.kind {
+            TokenKind::IncreaseKeyword => BinaryOperator::Add,
+            _ => BinaryOperator::Subtract,
+        };
+
+        Ok(Statement::VariableAssignment {
+            keyword_span: keyword.span(),
+            name: name_token.clone(),
+            value: Expression::BinaryOperation {
+                left: Box::new(Expression::Variable(name_token)),
+                operator,
+                right: Box::new(amount),
+            },
+        })
+    }
+
+    fn parse_swap_statement(&mut self) -> Result<Statement, Diagnostic> {
+        let keyword_span = self.expect(&[TokenKind::SwapKeyword])?.span();
+        let left = self.expect(&[TokenKind::Identifier])?;
+        self.expect(&[TokenKind::AndKeyword])?;
+        let right = self.expect(&[TokenKind::Identifier])?;
+
+        Ok(Statement::SwapStatement {
+            keyword_span,
+            left,
+            right,
+        })
+    }
+
     fn parse_if_statement(&mut self) -> Result<Statement, Diagnostic> {
+        let keyword_span = self.peek().span();
         let if_then_branch = self.parse_if_then_branch()?;
 
         let else_branch = if self.peek().kind == TokenKind::ElseKeyword {
             Some(self.parse_else_branch()?)
         }
+        // 'elif' continues the chain as a nested if that shares the final 'end'
+        else if self.peek().kind == TokenKind::ElifKeyword {
+            Some(self.parse_if_statement()?)
+        }
         else {
             self.expect(&[TokenKind::EndKeyword])?;
             None
@@ -272,16 +482,17 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         .map(Box::new);
 
         Ok(Statement::IfStatement {
+            keyword_span,
             if_then_branch,
             else_branch,
         })
     }
 
     fn parse_if_then_branch(&mut self) -> Result<IfThenBranch, Diagnostic> {
-        self.expect(&[TokenKind::IfKeyword])?;
+        self.expect(&[TokenKind::IfKeyword, TokenKind::ElifKeyword])?;
         let condition = self.parse_expression()?;
         self.expect(&[TokenKind::ThenKeyword])?;
-        let then_branch = self.parse_statements_until(&[TokenKind::ElseKeyword, TokenKind::EndKeyword])?;
+        let then_branch = self.parse_statements_until(&[TokenKind::ElseKeyword, TokenKind::ElifKeyword, TokenKind::EndKeyword])?;
 
         Ok(IfThenBranch { condition, then_branch: Box::new(then_branch) })
     }
@@ -299,20 +510,25 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     }
 
     fn parse_while_statement(&mut self) -> Result<Statement, Diagnostic> {
-        self.expect(&[TokenKind::WhileKeyword])?;
+        let keyword_span = self.expect(&[TokenKind::WhileKeyword])?.span();
         let condition = self.parse_expression()?;
         self.expect(&[TokenKind::DoKeyword])?;
         let body = self.parse_statements_until(&[TokenKind::EndKeyword])?;
         self.expect(&[TokenKind::EndKeyword])?;
 
         Ok(Statement::WhileStatement {
+            keyword_span,
             condition,
             body: Box::new(body),
         })
     }
 
     fn parse_for_statement(&mut self) -> Result<Statement, Diagnostic> {
-        self.expect(&[TokenKind::ForKeyword])?;
+        let keyword_span = self.expect(&[TokenKind::ForKeyword])?.span();
+        let retains_variable = self.peek().kind == TokenKind::LetKeyword;
+        if retains_variable {
+            self.advance(); // consume the 'let' keyword
+        }
         let variable = self.expect(&[TokenKind::Identifier])?;
         self.expect(&[TokenKind::FromKeyword])?;
         let start = self.parse_expression()?;
@@ -330,7 +546,9 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         self.expect(&[TokenKind::EndKeyword])?;
 
         Ok(Statement::ForStatement {
+            keyword_span,
             variable,
+            retains_variable,
             start,
             end,
             step,
@@ -338,39 +556,126 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         })
     }
 
+    fn parse_repeat_statement(&mut self) -> Result<Statement, Diagnostic> {
+        let keyword_span = self.expect(&[TokenKind::RepeatKeyword])?.span();
+        let count = self.parse_expression()?;
+        self.expect(&[TokenKind::TimesKeyword])?;
+        let body = self.parse_statements_until(&[TokenKind::EndKeyword])?;
+        self.expect(&[TokenKind::EndKeyword])?;
+
+        Ok(Statement::RepeatStatement {
+            keyword_span,
+            count,
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_when_statement(&mut self) -> Result<Statement, Diagnostic> {
+        let keyword_span = self.expect(&[TokenKind::WhenKeyword])?.span();
+        let subject = self.parse_expression()?;
+
+        let mut arms = vec![self.parse_when_arm()?];
+        while self.peek().kind == TokenKind::IsKeyword {
+            arms.push(self.parse_when_arm()?);
+        }
+
+        let default = if self.peek().kind == TokenKind::OtherwiseKeyword {
+            self.advance();
+            Some(Box::new(self.parse_statements_until(&[TokenKind::EndKeyword])?))
+        } else {
+            None
+        };
+        self.expect(&[TokenKind::EndKeyword])?;
+
+        Ok(Statement::WhenStatement {
+            keyword_span,
+            subject,
+            arms,
+            default,
+        })
+    }
+
+    fn parse_when_arm(&mut self) -> Result<(Expression, Statement), Diagnostic> {
+        self.expect(&[TokenKind::IsKeyword])?;
+        let value = self.parse_expression()?;
+        self.expect(&[TokenKind::ThenKeyword])?;
+        let body = self.parse_statements_until(&[TokenKind::IsKeyword, TokenKind::OtherwiseKeyword, TokenKind::EndKeyword])?;
+
+        Ok((value, body))
+    }
+
     fn parse_function_definition(&mut self) -> Result<Statement, Diagnostic> {
-        self.expect(&[TokenKind::DefineKeyword])?;
+        let keyword_span = self.expect(&[TokenKind::DefineKeyword])?.span();
         self.expect(&[TokenKind::FunctionKeyword])?;
         let function_name = self.expect(&[TokenKind::Identifier])?;
 
         let arguments = if self.peek().kind == TokenKind::WithKeyword {
             self.advance();
-            self.parse_tokens_list(TokenKind::Identifier, Some(TokenKind::Comma))?
+            self.parse_parameters()?
         }
         else {
             Vec::new()
         };
 
+        let return_type = if self.peek().kind == TokenKind::ReturnsKeyword {
+            self.advance();
+            let type_token = self.expect(&[TokenKind::IntKeyword, TokenKind::BoolKeyword])?;
+            Some(annotated_type(&type_token))
+        }
+        else {
+            None
+        };
+
         self.expect(&[TokenKind::AsKeyword])?;
         let body = self.parse_statements_until(&[TokenKind::EndKeyword])?;
         self.expect(&[TokenKind::EndKeyword])?;
 
         Ok(Statement::FunctionDefinition {
+            keyword_span,
             name: function_name,
             arguments,
+            return_type,
             body: Box::new(body),
         })
     }
 
-    fn parse_function_call(&mut self) -> Result<FunctionCallData, Diagnostic> {
-        let function_name = self.expect(&[TokenKind::Identifier])?;
+    // A statement starting with an identifier is a function call, or a misspelled keyword like 'whille'
+    fn parse_identifier_statement(&mut self) -> Result<Statement, Diagnostic> {
+        let identifier = self.expect(&[TokenKind::Identifier])?;
+
+        if self.is_call_after(&identifier) {
+            let arguments = self.parse_function_call_arguments()?;
+            return Ok(Statement::FunctionCall(FunctionCallData { function_name: identifier, arguments }));
+        }
 
-        let arguments = self.parse_function_call_arguments()?;
+        match suggest_statement_keyword(&identifier.value) {
+            Some(keyword) => {
+                // Skip the body of the block the keyword would have opened, up to its 'end'
+                let block_type = match keyword {
+                    TokenKind::IfKeyword => Some(BlockType::IfBlock),
+                    TokenKind::WhileKeyword => Some(BlockType::WhileBlock),
+                    TokenKind::ForKeyword => Some(BlockType::ForBlock),
+                    TokenKind::RepeatKeyword => Some(BlockType::RepeatBlock),
+                    TokenKind::WhenKeyword => Some(BlockType::WhenBlock),
+                    TokenKind::DefineKeyword => Some(BlockType::FunctionBlock),
+                    _ => None,
+                };
+                if let Some(block_type) = block_type {
+                    self.push_recovery_state(ErrorRecoveryState::RecoverFromBadBlock(block_type));
+                }
+                Err(Diagnostic::misspelled_keyword(identifier, keyword))
+            }
+            None => Err(Diagnostic::unexpected_token(vec![TokenKind::LeftParen], self.peek().clone())),
+        }
+    }
 
-        Ok(FunctionCallData { function_name, arguments })
+    // A '(' on the next line starts a new statement, so `let a be b` then `(1 + 2) * 3` is not a call to `b`
+    fn is_call_after(&mut self, identifier: &Token) -> bool {
+        let next_token = self.peek();
+        next_token.kind == TokenKind::LeftParen && next_token.position.line == identifier.position.line
     }
 
-    fn parse_function_call_arguments_list(&mut self) -> Result<Vec<Expression>, Diagnostic> {
+    fn parse_expressions_list(&mut self) -> Result<Vec<Expression>, Diagnostic> {
 
         let mut arguments = vec![self.parse_expression()?];
 
@@ -389,7 +694,7 @@ impl<I: Iterator<Item = Token>> Parser<I> {
             Vec::new()
         }
         else {
-            self.parse_function_call_arguments_list()?
+            self.parse_expressions_list()?
         };
         self.expect(&[TokenKind::RightParen])?;
 
@@ -411,8 +716,17 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         })
     }
 
+    // A malformed expression is reported and replaced by an error node so the enclosing statement keeps parsing
     fn parse_expression(&mut self) -> Result<Expression, Diagnostic> {
-        self.parse_expression_with_precedence(0)
+        let start_span = self.peek().span();
+
+        match self.parse_expression_with_precedence(0) {
+            Ok(expression) => Ok(expression),
+            Err(diagnostic) => {
+                self.diagnostics.report(diagnostic);
+                Ok(Expression::Error(self.recover_expression(start_span)))
+            }
+        }
     }
 
     fn parse_expression_with_precedence(&mut self, min_precedence: u8) -> Result<Expression, Diagnostic> {
@@ -425,10 +739,11 @@ impl<I: Iterator<Item = Token>> Parser<I> {
                 break;
             }
 
-            self.advance(); // consume the operator
+            let operator_token = self.advance(); // consume the operator
+            let op = self.check_spaced_comparison(op, &operator_token);
 
             // For left-associative operators, use precedence + 1 for the right operand
-            let next_min_prec = precedence + 1;
+            let next_min_prec = if op.is_right_associative() { precedence } else { precedence + 1 };
             let right = self.parse_expression_with_precedence(next_min_prec)?;
 
             left = Expression::BinaryOperation {
@@ -438,13 +753,69 @@ impl<I: Iterator<Item = Token>> Parser<I> {
             };
         }
 
+        self.check_misspelled_comparison()?;
+
         Ok(left)
     }
 
+    // '=<' and '=>' lex as '=' followed by '<' or '>'
+    fn check_misspelled_comparison(&mut self) -> Result<(), Diagnostic> {
+        if self.peek().kind != TokenKind::Equal {
+            return Ok(());
+        }
+
+        let equal_token = self.advance();
+        let suggestion = match self.peek().kind {
+            TokenKind::LessThan => "<=",
+            TokenKind::GreaterThan => ">=",
+            _ => return Err(Diagnostic::unexpected_token(vec![TokenKind::EqualEqual], equal_token)),
+        };
+        let operator_token = self.advance();
+
+        Err(Diagnostic::misspelled_operator(
+            format!("{}{}", equal_token.value, operator_token.value),
+            suggestion.to_string(),
+            equal_token.span().union(&operator_token.span()),
+        ))
+    }
+
+    // '> =' and '< =' are reported, then parsed as the '>=' and '<=' they were meant to be
+    fn check_spaced_comparison(&mut self, op: BinaryOperator, operator_token: &Token) -> BinaryOperator {
+        let intended = match op {
+            BinaryOperator::LessThan => BinaryOperator::LessThanOrEqual,
+            BinaryOperator::GreaterThan => BinaryOperator::GreaterThanOrEqual,
+            _ => return op,
+        };
+        if self.peek().kind != TokenKind::Equal {
+            return op;
+        }
+
+        let equal_token = self.advance();
+        self.diagnostics.report(Diagnostic::misspelled_operator(
+            format!("{} {}", operator_token.value, equal_token.value),
+            intended.to_string(),
+            operator_token.span().union(&equal_token.span()),
+        ));
+        intended
+    }
+
+    // Prefix operators apply to the operand right after them, before any binary operator: `not a == b` is `(not a) == b`
     fn parse_unary_expression(&mut self) -> Result<Expression, Diagnostic> {
         
         if let Ok(op) = UnaryOperator::try_from(self.peek().kind) {
-            self.advance(); // consume the operator
+            let operator_token = self.advance(); // consume the operator
+
+            // A minus directly followed by a number is folded into a negative literal
+            if op == UnaryOperator::Negate && self.peek().kind == TokenKind::Number {
+                let number_token = self.advance();
+                let span = operator_token.span().union(&number_token.span());
+                let value = integer_value(format!("-{}", number_digits(&number_token)?), &span)?;
+                return Ok(Expression::Literal {
+                    value: Literal::Number(value),
+                    span,
+                });
+            }
+
             let operand = self.parse_unary_expression()?;
             return Ok(Expression::UnaryOperation {
                 operator: op,
@@ -459,11 +830,64 @@ impl<I: Iterator<Item = Token>> Parser<I> {
 
         let next_token = self.peek();
         
-        if next_token.kind == TokenKind::LeftParen {
-            return self.parse_grouped_expression();
+        let mut expression = match next_token.kind {
+            TokenKind::LeftParen => self.parse_grouped_expression()?,
+            TokenKind::LeftBracket => self.parse_list_expression()?,
+            TokenKind::IfKeyword => self.parse_conditional_expression()?,
+            _ => self.parse_literal_expression()?,
+        };
+
+        while self.peek().kind == TokenKind::LeftBracket {
+            expression = self.parse_index_expression(expression)?;
         }
 
-        self.parse_literal_expression()
+        Ok(expression)
+    }
+
+    fn parse_list_expression(&mut self) -> Result<Expression, Diagnostic> {
+        let left_bracket = self.expect(&[TokenKind::LeftBracket])?;
+
+        let elements = if self.peek().kind == TokenKind::RightBracket {
+            Vec::new()
+        }
+        else {
+            self.parse_expressions_list()?
+        };
+        let right_bracket = self.expect(&[TokenKind::RightBracket])?;
+
+        Ok(Expression::List {
+            elements,
+            span: left_bracket.span().union(&right_bracket.span()),
+        })
+    }
+
+    // The else branch extends as far as possible, so `if c then 1 else 2 + 3` adds 3 to the else branch only
+    fn parse_conditional_expression(&mut self) -> Result<Expression, Diagnostic> {
+        let if_token = self.expect(&[TokenKind::IfKeyword])?;
+        let condition = self.parse_expression()?;
+        self.expect(&[TokenKind::ThenKeyword])?;
+        let then_expr = self.parse_expression()?;
+        self.expect(&[TokenKind::ElseKeyword])?;
+        let else_expr = self.parse_expression()?;
+
+        Ok(Expression::Conditional {
+            span: if_token.span().union(&else_expr.span()),
+            condition: Box::new(condition),
+            then_expr: Box::new(then_expr),
+            else_expr: Box::new(else_expr),
+        })
+    }
+
+    fn parse_index_expression(&mut self, target: Expression) -> Result<Expression, Diagnostic> {
+        self.expect(&[TokenKind::LeftBracket])?;
+        let index = self.parse_expression()?;
+        let right_bracket = self.expect(&[TokenKind::RightBracket])?;
+
+        Ok(Expression::Index {
+            span: target.span().union(&right_bracket.span()),
+            target: Box::new(target),
+            index: Box::new(index),
+        })
     }
 
     fn parse_grouped_expression(&mut self) -> Result<Expression, Diagnostic> {
@@ -471,16 +895,23 @@ impl<I: Iterator<Item = Token>> Parser<I> {
         self.expect(&[TokenKind::LeftParen])?;
         let expr = self.parse_expression()?;
         self.expect(&[TokenKind::RightParen])?;
+
+        // '((expr))' groups nothing more than '(expr)'
+        if matches!(expr, Expression::Grouped(_)) {
+            return Ok(expr);
+        }
         Ok(Expression::Grouped(Box::new(expr)))
     }
 
     fn parse_literal_expression(&mut self) -> Result<Expression, Diagnostic> {
+        let previous_line = self.previous_line;
         let next_token = self.peek();
 
         match next_token.kind {
             TokenKind::Number => {
                 let number_token: Token = self.advance();
-                Ok(Expression::Literal { value: Literal::Number(number_token.value.parse().unwrap()), span: number_token.span() })
+                let value = integer_value(number_digits(&number_token)?, &number_token.span())?;
+                Ok(Expression::Literal { value: Literal::Number(value), span: number_token.span() })
             }
             TokenKind::TrueKeyword => {
                 let token = self.advance(); // consume the 'true' keyword
@@ -492,7 +923,7 @@ impl<I: Iterator<Item = Token>> Parser<I> {
             }
             TokenKind::Identifier => {
                 let identifier_token = self.advance();
-                if self.peek().kind != TokenKind::LeftParen {
+                if !self.is_call_after(&identifier_token) {
                     Ok(Expression::Variable(identifier_token))
                 }
                 else {
@@ -503,6 +934,12 @@ impl<I: Iterator<Item = Token>> Parser<I> {
                     }))
                 }
             }
+            // 'set a to set b to 5': the nested assignment is consumed whole so it is reported once
+            // A `set` on a new line starts the next statement, the value is then missing rather than nested
+            TokenKind::SetKeyword if next_token.position.line == previous_line => {
+                let assignment = self.parse_variable_assignement()?;
+                Err(Diagnostic::assignment_in_expression(assignment.span()))
+            }
             _ => {
                 Err(Diagnostic::unexpected_token(
                     vec![TokenKind::Number, TokenKind::Identifier, TokenKind::TrueKeyword, TokenKind::FalseKeyword],
@@ -511,4 +948,44 @@ impl<I: Iterator<Item = Token>> Parser<I> {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// The digits of a number literal without its `_` separators, which must each sit between two digits
+fn number_digits(number_token: &Token) -> Result<String, Diagnostic> {
+    let number = &number_token.value;
+    if number.ends_with('_') || number.contains("__") {
+        return Err(Diagnostic::invalid_digit_separator(number_token.clone()));
+    }
+    Ok(number.replace('_', ""))
+}
+
+fn integer_value(literal: String, span: &TextSpan) -> Result<i64, Diagnostic> {
+    literal.parse().map_err(|_| Diagnostic::integer_literal_out_of_range(literal, span.clone()))
+}
+
+fn annotated_type(type_token: &Token) -> Type {
+    match type_token.kind {
+        TokenKind::BoolKeyword => Type::Bool,
+        _ => Type::Int,
+    }
+}
+
+fn zero_value(type_token: &Token) -> Expression {
+    let value = match type_token.kind {
+        TokenKind::BoolKeyword => Literal::Boolean(false),
+        _ => Literal::Number(0),
+    };
+    Expression::Literal { value, span: type_token.span() }
+}
+
+fn suggest_statement_keyword(identifier: &str) -> Option<TokenKind> {
+    // Short keywords only tolerate one typo, otherwise most short identifiers would match
+    let max_distance = |keyword: &str| if keyword.len() <= 4 { 1 } else { 2 };
+
+    STATEMENT_KEYWORDS
+        .iter()
+        .map(|keyword| (*keyword, edit_distance(identifier, &keyword.to_string())))
+        .filter(|(keyword, distance)| *distance <= max_distance(&keyword.to_string()))
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(keyword, _)| keyword)
+}
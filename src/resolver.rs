@@ -1,4 +1,10 @@
-use crate::{ast::{Ast, AstExplorer}, diagnostic::{Diagnostic, Diagnostics}, symbols_table::{FunctionSymbol, ScopeId, SymbolsTable, VariableSymbol}, types::{self, Type}, BlockType};
+use crate::{ast::{expression::{BinaryOperator, Expression, Literal, UnaryOperator}, statement::{Parameter, Statement}, Ast, AstExplorer}, diagnostic::{Diagnostic, Diagnostics}, interpreter::native_functions, lexer::TextSpan, symbols_table::{FunctionSymbol, ScopeId, SymbolsTable, VariableKind, VariableSymbol}, types::{self, Type, TypeMap}, BlockType};
+
+pub struct ResolverOutput {
+    pub symbols_table: SymbolsTable,
+    pub type_map: TypeMap,
+    pub warnings: Diagnostics,
+}
 
 pub struct Resolver {
     symbols_table: SymbolsTable,
@@ -7,79 +13,298 @@ pub struct Resolver {
     block_type_stack: Vec<BlockType>,
     current_block_type: Option<BlockType>,
     type_accumulator: Type,
+    type_map: TypeMap,
+    // Return type found so far for each function being resolved, the innermost last
+    return_types: Vec<Type>,
+    // Globals of an incremental compilation can still be used by code appended later
+    retains_globals: bool,
+    // Spans of the statements being resolved, the innermost last. A new scope covers the innermost one.
+    statement_spans: Vec<TextSpan>,
 }
 
 impl Resolver {
     pub fn new() -> Self {
+        let mut symbols_table = SymbolsTable::new();
+        for native in native_functions() {
+            symbols_table.define_function(FunctionSymbol {
+                identifier: native.name.to_string(),
+                parameters: native.parameters.iter().map(|(parameter, _)| parameter.to_string()).collect(),
+                parameter_types: native.parameters.into_iter().map(|(_, parameter_type)| parameter_type).collect(),
+                return_type: native.return_type,
+            });
+        }
+
         Self {
-            symbols_table: SymbolsTable::new(),
+            retains_globals: false,
+            ..Self::with_symbols_table(symbols_table)
+        }
+    }
+
+    // Resolves more code against the symbols of an earlier compilation, e.g. the next cell of a notebook
+    pub fn with_symbols_table(symbols_table: SymbolsTable) -> Self {
+        Self {
+            symbols_table,
             current_scope_id: ScopeId(0),
             diagnostics: Diagnostics::new(),
             block_type_stack: Vec::new(),
             current_block_type: None,
             type_accumulator: Type::Unresolved,
+            type_map: TypeMap::new(),
+            return_types: Vec::new(),
+            retains_globals: true,
+            statement_spans: Vec::new(),
         }
     }
 
-    pub fn resolve(mut self, ast: &Ast) -> Result<SymbolsTable, Diagnostics> {
+    pub fn resolve(mut self, ast: &Ast) -> Result<ResolverOutput, Diagnostics> {
+        self.declare_functions(ast);
+        self.infer_return_types(ast);
         self.explore_ast(ast);
+        if !self.retains_globals {
+            self.report_unused_variables();
+        }
 
         if self.diagnostics.has_errors() {
             Err(self.diagnostics)
         } else {
-            Ok(self.symbols_table)
+            Ok(ResolverOutput {
+                symbols_table: self.symbols_table,
+                type_map: self.type_map,
+                warnings: self.diagnostics,
+            })
+        }
+    }
+
+    // Top-level functions can be called before their definition, as the interpreter collects them all before running
+    fn declare_functions(&mut self, ast: &Ast) {
+        let defines_main = ast.iter().any(|statement| matches!(statement, Statement::FunctionDefinition { name, .. } if name.value == "main"));
+
+        for statement in ast.statements() {
+            if let Statement::FunctionDefinition { name, arguments, return_type, body, .. } = statement {
+                // A top-level `main` is called without arguments to start the program
+                if name.value == "main" && !arguments.is_empty() {
+                    self.diagnostics.report(Diagnostic::main_with_parameters(name.clone()));
+                }
+                self.define_function(name, arguments, return_type.as_ref(), body);
+            }
+            // The interpreter runs the top-level declarations and then calls `main` instead of the other statements
+            else if defines_main && !statement.is_declaration() {
+                self.diagnostics.report(Diagnostic::skipped_by_main(statement.span()));
+            }
         }
     }
 
+    // A function's return type can depend on functions defined after it, or on itself through mutual recursion,
+    // so every top-level body is resolved again until no return or parameter type changes.
+    // Types only go from unresolved to resolved, which bounds the number of rounds.
+    fn infer_return_types(&mut self, ast: &Ast) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for statement in ast.statements() {
+                if let Statement::FunctionDefinition { name, .. } = statement {
+                    let mut resolver = Resolver::with_symbols_table(self.symbols_table.clone());
+                    resolver.visit_statement(statement);
+
+                    let inferred = resolver.symbols_table
+                        .lookup_function(&name.value)
+                        .map(|function| (function.return_type.clone(), function.parameter_types.clone()));
+                    if let (Some(function), Some((return_type, parameter_types))) = (self.symbols_table.lookup_function_mut(&name.value), inferred)
+                        && (function.return_type != return_type || function.parameter_types != parameter_types) {
+                        function.return_type = return_type;
+                        function.parameter_types = parameter_types;
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // The first definition of a name is kept, later ones are reported
+    fn define_function(&mut self, name: &crate::lexer::Token, arguments: &[Parameter], return_type: Option<&Type>, body: &Statement) {
+        if self.symbols_table.lookup_function(&name.value).is_some() {
+            self.diagnostics.report(Diagnostic::function_redefinition(name.clone()));
+            return;
+        }
+
+        self.symbols_table.define_function(FunctionSymbol {
+            identifier: name.value.clone(),
+            parameters: arguments.iter().map(|arg| arg.name.value.clone()).collect(),
+            // Types that are not annotated will be inferred later
+            parameter_types: arguments.iter().map(|arg| arg.type_annotation.clone().unwrap_or(Type::Unresolved)).collect(),
+            return_type: return_type.cloned().unwrap_or(if returns_value(body) { Type::Unresolved } else { Type::Unit }),
+        });
+    }
+
+    // Both sides of a swap are read and assigned, so each must be a declared, non constant variable
+    fn swapped_variable_type(&mut self, name: &crate::lexer::Token) -> Option<Type> {
+        let Some(variable_symbol) = self.symbols_table.lookup_variable_mut(&name.value, self.current_scope_id) else {
+            self.diagnostics.report(Diagnostic::undefined_variable(name.clone()));
+            return None;
+        };
+
+        variable_symbol.is_used = true;
+        if variable_symbol.is_const {
+            self.diagnostics.report(Diagnostic::assignment_to_const(name.clone()));
+        }
+        Some(variable_symbol.sym_type.clone())
+    }
+
     fn enter_scope(&mut self) {
-        self.current_scope_id = self.symbols_table.enter_scope(self.current_scope_id);
+        let span = self.statement_spans.last().cloned().expect("A scope is always entered by a statement");
+        self.current_scope_id = self.symbols_table.enter_scope(self.current_scope_id, span);
     }
     fn exit_scope(&mut self) {
+        self.report_unused_variables();
         self.current_scope_id = self.symbols_table.exit_scope(self.current_scope_id);
     }
 
-    fn is_inside_block(&self, block_type: BlockType) -> bool {
-        self.block_type_stack.iter().any(|&bt| bt == block_type)
+    fn report_unused_variables(&mut self) {
+        for symbol in self.symbols_table.unused_variables(self.current_scope_id) {
+            self.diagnostics.report(Diagnostic::unused_variable(symbol.identifier.clone(), symbol.span.clone()));
+        }
     }
-}
 
-impl AstExplorer for Resolver {
-    fn visit_variable_declaration(&mut self, name: &crate::lexer::Token, value: &crate::ast::expression::Expression) {
-        
+    fn declare_variable(&mut self, name: &crate::lexer::Token, type_annotation: Option<&Type>, value: &crate::ast::expression::Expression, is_const: bool) {
         if self.symbols_table.lookup_variable_in_scope_only(&name.value, self.current_scope_id).is_some() {
             self.diagnostics.report(Diagnostic::variable_redefinition(name.clone()));
         }
+        else if self.symbols_table.lookup_variable(&name.value, self.current_scope_id).is_some() {
+            self.diagnostics.report(Diagnostic::variable_shadowing(name.clone()));
+        }
         
         self.visit_expression(value);
 
+        // The annotation wins over the value's type, so a mismatch is reported once here rather than at every use
+        let sym_type = match type_annotation {
+            Some(annotation) => {
+                if !annotation.accepts(&self.type_accumulator) {
+                    self.diagnostics.report(Diagnostic::variable_type_mismatch(name.clone(), annotation.clone(), self.type_accumulator.clone()));
+                }
+                annotation.clone()
+            }
+            None => self.type_accumulator.clone(),
+        };
+
         self.symbols_table.define_variable(VariableSymbol {
             identifier: name.value.clone(),
-            sym_type: self.type_accumulator.clone(),
+            sym_type,
+            kind: VariableKind::Local,
+            span: name.span(),
+            is_used: false,
+            is_const,
         }, self.current_scope_id);
-        
+    }
+
+    // Only a return directly in the block makes its following siblings unreachable
+    fn report_unreachable_statements(&mut self, statements: &[Statement]) {
+        let first_unreachable = statements
+            .iter()
+            .position(|statement| matches!(statement, Statement::ReturnStatement { .. }))
+            .and_then(|return_index| statements.get(return_index + 1));
+
+        if let Some(statement) = first_unreachable {
+            self.diagnostics.report(Diagnostic::unreachable_code(statement.span()));
+        }
+    }
+
+    // A parameter without annotation takes the type of the first operator it is used with, e.g. `int` in `n % 2`
+    fn infer_parameter_type(&mut self, operand: &Expression, operand_type: &Type) {
+        let Expression::Variable(name) = ungrouped(operand) else {
+            return;
+        };
+        if let Some(variable_symbol) = self.symbols_table.lookup_variable_mut(&name.value, self.current_scope_id)
+            && variable_symbol.kind == VariableKind::Parameter
+            && variable_symbol.sym_type == Type::Unresolved {
+            variable_symbol.sym_type = operand_type.clone();
+        }
+    }
+
+    fn is_inside_block(&self, block_type: BlockType) -> bool {
+        self.block_type_stack.contains(&block_type)
+    }
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AstExplorer for Resolver {
+    fn visit_statement(&mut self, statement: &Statement) {
+        if let Statement::BlockStatement { statements, .. } = statement {
+            self.report_unreachable_statements(statements);
+        }
+        self.statement_spans.push(statement.span());
+        self.visit_statement_impl(statement);
+        self.statement_spans.pop();
+    }
+
+    fn visit_expression(&mut self, expression: &crate::ast::expression::Expression) {
+        self.visit_expression_impl(expression);
+
+        // Reported once here, the unresolved type then keeps the enclosing expression from reporting it again
+        if let Expression::FunctionCall(data) = expression
+            && self.type_accumulator == Type::Unit {
+            self.diagnostics.report(Diagnostic::unit_value_used(data.function_name.clone()));
+            self.type_accumulator = Type::Unresolved;
+        }
+        self.type_map.record(expression.span(), self.type_accumulator.clone());
+    }
+
+    fn visit_variable_declaration(&mut self, name: &crate::lexer::Token, type_annotation: Option<&Type>, value: &crate::ast::expression::Expression) {
+        self.declare_variable(name, type_annotation, value, false);
+    }
+
+    fn visit_const_declaration(&mut self, name: &crate::lexer::Token, value: &crate::ast::expression::Expression) {
+        self.declare_variable(name, None, value, true);
     }
 
     fn visit_variable_assignement(&mut self, name: &crate::lexer::Token, value: &crate::ast::expression::Expression) {
         self.visit_expression(value);
 
-        if let Some(variable_symbol) = self.symbols_table.lookup_variable(&name.value, self.current_scope_id) {
-            if variable_symbol.sym_type != self.type_accumulator {
+        if let Some(variable_symbol) = self.symbols_table.lookup_variable_mut(&name.value, self.current_scope_id) {
+            if variable_symbol.is_const {
+                self.diagnostics.report(Diagnostic::assignment_to_const(name.clone()));
+            }
+            else if !variable_symbol.sym_type.accepts(&self.type_accumulator) {
                 self.diagnostics.report(Diagnostic::variable_type_mismatch(name.clone(), variable_symbol.sym_type.clone(), self.type_accumulator.clone()));
             }
+            else if !variable_symbol.sym_type.is_resolved() && self.type_accumulator.is_resolved() {
+                // Refines the type of a variable initialized with `[]` or an unresolved expression
+                variable_symbol.sym_type = self.type_accumulator.clone();
+            }
         }
         else {
             self.diagnostics.report(Diagnostic::undefined_variable(name.clone()));
         }
     }
+    fn visit_swap_statement(&mut self, left: &crate::lexer::Token, right: &crate::lexer::Token) {
+        let left_type = self.swapped_variable_type(left);
+        let right_type = self.swapped_variable_type(right);
+
+        if let (Some(left_type), Some(right_type)) = (left_type, right_type)
+            && !left_type.accepts(&right_type) {
+            self.diagnostics.report(Diagnostic::variable_type_mismatch(right.clone(), left_type, right_type));
+        }
+    }
 
     fn visit_if_statement(&mut self, condition: &crate::ast::expression::Expression, then_branch: &crate::ast::statement::Statement, else_branch: Option<&crate::ast::statement::Statement>) {
         self.current_block_type = Some(BlockType::IfBlock);
         self.visit_expression(condition);
 
-        if self.type_accumulator != Type::Bool {
+        if !Type::Bool.accepts(&self.type_accumulator) {
             self.diagnostics.report(Diagnostic::expression_type_mismatch(Type::Bool, self.type_accumulator.clone(), condition.span()));
         }
 
+        match (fold_boolean_constant(condition), else_branch) {
+            (Some(true), Some(else_branch)) => self.diagnostics.report(Diagnostic::unreachable_branch(true, else_branch.span())),
+            (Some(false), _) => self.diagnostics.report(Diagnostic::unreachable_branch(false, then_branch.span())),
+            _ => {}
+        }
+
         self.visit_statement(then_branch);
         if let Some(else_branch) = else_branch {
             self.current_block_type = Some(BlockType::ElseBlock);
@@ -90,14 +315,14 @@ impl AstExplorer for Resolver {
     fn visit_while_statement(&mut self, condition: &crate::ast::expression::Expression, body: &crate::ast::statement::Statement) {
         self.current_block_type = Some(BlockType::WhileBlock);
         self.visit_expression(condition);
-        if self.type_accumulator != Type::Bool {
+        if !Type::Bool.accepts(&self.type_accumulator) {
             self.diagnostics.report(Diagnostic::expression_type_mismatch(Type::Bool, self.type_accumulator.clone(), condition.span()));
         }
         self.visit_statement(body);
 
     }
 
-    fn visit_for_statement(&mut self, variable: &crate::lexer::Token, start: &crate::ast::expression::Expression, end: &crate::ast::expression::Expression, step: &Option<crate::ast::expression::Expression>, body: &crate::ast::statement::Statement) {
+    fn visit_for_statement(&mut self, variable: &crate::lexer::Token, retains_variable: bool, start: &crate::ast::expression::Expression, end: &crate::ast::expression::Expression, step: &Option<crate::ast::expression::Expression>, body: &crate::ast::statement::Statement) {
         self.current_block_type = Some(BlockType::ForBlock);
 
         self.visit_expression(start);
@@ -105,7 +330,7 @@ impl AstExplorer for Resolver {
         self.visit_expression(end);
         let end_type = self.type_accumulator.clone();
         
-        if start_type != end_type {
+        if !start_type.accepts(&end_type) {
             self.diagnostics.report(Diagnostic::variable_type_mismatch(variable.clone(), start_type.clone(), end_type.clone()));
         }
 
@@ -113,21 +338,68 @@ impl AstExplorer for Resolver {
             self.visit_expression(step_expr);
 
             let step_type = self.type_accumulator.clone();
-            if step_type != start_type {
+            if !start_type.accepts(&step_type) {
                 self.diagnostics.report(Diagnostic::variable_type_mismatch(variable.clone(), start_type.clone(), step_type.clone()));
             }
 
-            if end_type != step_type {
+            if !end_type.accepts(&step_type) {
                 self.diagnostics.report(Diagnostic::expression_type_mismatch(end_type.clone(), step_type.clone(), step_expr.span()));
             }
+
+            if fold_integer_constant(step_expr) == Some(0) {
+                self.diagnostics.report(Diagnostic::zero_step(step_expr.span()));
+            }
         }
-        self.enter_scope();
+        if retains_variable {
+            if self.symbols_table.lookup_variable_in_scope_only(&variable.value, self.current_scope_id).is_some() {
+                self.diagnostics.report(Diagnostic::variable_redefinition(variable.clone()));
+            }
+        }
+        else {
+            self.enter_scope();
+        }
+
         self.symbols_table.define_variable(VariableSymbol {
             identifier: variable.value.clone(),
             sym_type: start_type,
+            kind: VariableKind::LoopVariable,
+            span: variable.span(),
+            is_used: false,
+            is_const: false,
         }, self.current_scope_id);
         self.visit_statement(body);
-        self.exit_scope();
+
+        if !retains_variable {
+            self.exit_scope();
+        }
+    }
+
+    fn visit_repeat_statement(&mut self, count: &crate::ast::expression::Expression, body: &crate::ast::statement::Statement) {
+        self.current_block_type = Some(BlockType::RepeatBlock);
+        self.visit_expression(count);
+        if !Type::Int.accepts(&self.type_accumulator) {
+            self.diagnostics.report(Diagnostic::expression_type_mismatch(Type::Int, self.type_accumulator.clone(), count.span()));
+        }
+        self.visit_statement(body);
+    }
+
+    fn visit_when_statement(&mut self, subject: &crate::ast::expression::Expression, arms: &[(crate::ast::expression::Expression, crate::ast::statement::Statement)], default: Option<&crate::ast::statement::Statement>) {
+        self.visit_expression(subject);
+        let subject_type = self.type_accumulator.clone();
+
+        for (value, body) in arms {
+            self.visit_expression(value);
+            if !subject_type.accepts(&self.type_accumulator) {
+                self.diagnostics.report(Diagnostic::expression_type_mismatch(subject_type.clone(), self.type_accumulator.clone(), value.span()));
+            }
+            self.current_block_type = Some(BlockType::WhenBlock);
+            self.visit_statement(body);
+        }
+
+        if let Some(default) = default {
+            self.current_block_type = Some(BlockType::WhenBlock);
+            self.visit_statement(default);
+        }
     }
 
     fn block_statement_on_enter(&mut self) {
@@ -154,7 +426,9 @@ impl AstExplorer for Resolver {
     fn visit_variable_expression(&mut self, name: &crate::lexer::Token) {
         if let Some(symbol) = self.symbols_table.lookup_variable(&name.value, self.current_scope_id) {
             self.type_accumulator = symbol.sym_type.clone();
+            self.symbols_table.mark_variable_used(&name.value, self.current_scope_id);
         } else {
+           self.type_accumulator = Type::Unresolved;
            self.diagnostics.report(Diagnostic::undefined_variable(name.clone()));
         }
     }
@@ -165,65 +439,332 @@ impl AstExplorer for Resolver {
         self.visit_expression(right);
         let right_type = self.type_accumulator.clone();
 
+        match types::binary_operand_type(operator) {
+            Some(operand_type) => {
+                self.infer_parameter_type(left, &operand_type);
+                self.infer_parameter_type(right, &operand_type);
+            }
+            // Compared to a value of a known type, e.g. `n == 0`
+            None => {
+                if right_type.is_resolved() {
+                    self.infer_parameter_type(left, &right_type);
+                }
+                if left_type.is_resolved() {
+                    self.infer_parameter_type(right, &left_type);
+                }
+            }
+        }
+
+        // Logical operands are checked one by one so the error points at the operand that isn't a bool
+        if matches!(operator, BinaryOperator::And | BinaryOperator::Or) {
+            for (operand, operand_type) in [(left, left_type), (right, right_type)] {
+                if !Type::Bool.accepts(&operand_type) {
+                    self.diagnostics.report(Diagnostic::expression_type_mismatch(Type::Bool, operand_type, operand.span()));
+                }
+            }
+            self.type_accumulator = Type::Bool;
+            return;
+        }
+
         self.type_accumulator = types::resolve_binary_operation_type(&left_type, &right_type, operator);
 
         if self.type_accumulator == Type::Unresolved {
             self.diagnostics.report(Diagnostic::incompatible_binary_operation(left_type, right_type, *operator, left.span().union(&right.span())));
         }
+
+        let self_comparison_result = match operator {
+            BinaryOperator::Equal | BinaryOperator::LessThanOrEqual | BinaryOperator::GreaterThanOrEqual => Some(true),
+            BinaryOperator::NotEqual | BinaryOperator::LessThan | BinaryOperator::GreaterThan => Some(false),
+            _ => None,
+        };
+        if let Some(result) = self_comparison_result
+            && references_variable(left)
+            && is_same_expression(left, right) {
+            self.diagnostics.report(Diagnostic::self_comparison(*operator, result, left.span().union(&right.span())));
+        }
     }
 
     fn visit_unary_operation(&mut self, operator: &crate::ast::expression::UnaryOperator, operand: &crate::ast::expression::Expression) {
         self.visit_expression(operand);
         let operand_type = self.type_accumulator.clone();
+        self.infer_parameter_type(operand, &types::unary_operand_type(operator));
         self.type_accumulator = types::resolve_unary_operation_type(&operand_type, operator);
         if self.type_accumulator == Type::Unresolved {
             self.diagnostics.report(Diagnostic::incompatible_unary_operation(operand_type, *operator, operand.span()));
         }
     }
     
-    fn visit_function_definition(&mut self, name: &crate::lexer::Token, arguments: &[crate::lexer::Token], body: &crate::ast::statement::Statement) {
-        self.symbols_table.define_function(FunctionSymbol {
-            identifier: name.value.clone(),
-            parameters: arguments.iter().map(|arg| arg.value.clone()).collect(),
-        });
+    fn visit_function_definition(&mut self, name: &crate::lexer::Token, arguments: &[Parameter], return_type: Option<&Type>, body: &crate::ast::statement::Statement) {
+        // Top-level functions were already declared by `declare_functions`
+        if self.current_scope_id != ScopeId(0) {
+            self.define_function(name, arguments, return_type, body);
+        }
+
+        // Only a body without any statement is reported, a body that never returns is still a valid procedure
+        if let Statement::BlockStatement { statements, .. } = body
+            && statements.is_empty() {
+            self.diagnostics.report(Diagnostic::empty_function_body(name.clone()));
+        }
 
         self.enter_scope();
         self.current_block_type = Some(BlockType::FunctionBlock);
 
-        arguments
-            .iter()
-            .for_each(|argument| 
+        // Annotated types, or the ones inferred by an earlier pass over the body
+        let parameter_types = self.symbols_table
+            .lookup_function(&name.value)
+            .map(|function| function.parameter_types.clone())
+            .unwrap_or_default();
+
+        for (index, argument) in arguments.iter().enumerate() {
+            if self.symbols_table.lookup_variable_in_scope_only(&argument.name.value, self.current_scope_id).is_some() {
+                self.diagnostics.report(Diagnostic::duplicate_parameter(argument.name.clone()));
+                continue;
+            }
+
             self.symbols_table.define_variable(VariableSymbol {
-            identifier: argument.value.clone(),
-            sym_type: Type::Unresolved, // Type will be inferred later
-        }, self.current_scope_id));
+                identifier: argument.name.value.clone(),
+                sym_type: parameter_types.get(index).cloned().unwrap_or(Type::Unresolved),
+                kind: VariableKind::Parameter,
+                span: argument.name.span(),
+                is_used: false,
+                is_const: false,
+            }, self.current_scope_id);
+        }
         
+        let declared_return_type = self.symbols_table
+            .lookup_function(&name.value)
+            .map_or(Type::Unresolved, |function| function.return_type.clone());
+        self.return_types.push(declared_return_type);
+
         self.visit_statement(body);
+
+        let inferred_parameter_types: Vec<Type> = arguments
+            .iter()
+            .map(|argument| self.symbols_table
+                .lookup_variable_in_scope_only(&argument.name.value, self.current_scope_id)
+                .map_or(Type::Unresolved, |parameter| parameter.sym_type.clone()))
+            .collect();
         self.exit_scope();
+
+        let return_type = self.return_types.pop().unwrap_or(Type::Unresolved);
+        if let Some(function) = self.symbols_table.lookup_function_mut(&name.value) {
+            if function.return_type == Type::Unresolved {
+                function.return_type = return_type;
+            }
+            for (parameter_type, inferred) in function.parameter_types.iter_mut().zip(inferred_parameter_types) {
+                if *parameter_type == Type::Unresolved {
+                    *parameter_type = inferred;
+                }
+            }
+            // Falling off the end would give the caller no value
+            if function.return_type != Type::Unit && !returns_on_every_path(body) {
+                self.diagnostics.report(Diagnostic::missing_return(name.clone()));
+            }
+        }
     }
     
     fn visit_function_call(&mut self, function_name: &crate::lexer::Token, arguments: &[crate::ast::expression::Expression]) {
+        let mut return_type = Type::Unresolved;
+        let mut parameter_types = Vec::new();
         if let Some(function_symbol) = self.symbols_table.lookup_function(&function_name.value) {
             if function_symbol.parameters.len() != arguments.len() {
                 self.diagnostics.report(Diagnostic::function_arguments_mismatch(function_name.clone(), function_symbol.parameters.len(), arguments.len()));
             }
+            return_type = function_symbol.return_type.clone();
+            parameter_types = function_symbol.parameter_types.clone();
         } 
         else {
             self.diagnostics.report(Diagnostic::undefined_function(function_name.clone()));
         }
 
-        for argument in arguments {
+        for (index, argument) in arguments.iter().enumerate() {
             self.visit_expression(argument);
+            if let Some(parameter_type) = parameter_types.get(index) 
+                && !parameter_type.accepts(&self.type_accumulator) {
+                self.diagnostics.report(Diagnostic::expression_type_mismatch(parameter_type.clone(), self.type_accumulator.clone(), argument.span()));
+            }
         }
+
+        self.type_accumulator = return_type;
     }
 
     fn visit_return_statement(&mut self, span: crate::lexer::TextSpan, expression: &Option<crate::ast::expression::Expression>) {
         if self.is_inside_block(BlockType::FunctionBlock) {
             if let Some(expr) = expression {
                 self.visit_expression(expr);
+
+                // The first resolved return fixes the function's type, later ones must match it
+                if let Some(return_type) = self.return_types.last_mut() {
+                    if *return_type == Type::Unresolved {
+                        *return_type = self.type_accumulator.clone();
+                    }
+                    else if !return_type.accepts(&self.type_accumulator) {
+                        self.diagnostics.report(Diagnostic::expression_type_mismatch(return_type.clone(), self.type_accumulator.clone(), expr.span()));
+                    }
+                }
             }
         } else {
             self.diagnostics.report(Diagnostic::return_outside_function(span));
         }
     }
-}
\ No newline at end of file
+
+    fn visit_list_expression(&mut self, elements: &[Expression]) {
+        let mut element_type = Type::Unresolved;
+
+        for element in elements {
+            self.visit_expression(element);
+            if element_type == Type::Unresolved {
+                element_type = self.type_accumulator.clone();
+            }
+            else if !element_type.accepts(&self.type_accumulator) {
+                self.diagnostics.report(Diagnostic::expression_type_mismatch(element_type.clone(), self.type_accumulator.clone(), element.span()));
+            }
+        }
+
+        self.type_accumulator = Type::List(Box::new(element_type));
+    }
+
+    fn visit_index_expression(&mut self, target: &Expression, index: &Expression) {
+        self.visit_expression(index);
+        if !Type::Int.accepts(&self.type_accumulator) {
+            self.diagnostics.report(Diagnostic::expression_type_mismatch(Type::Int, self.type_accumulator.clone(), index.span()));
+        }
+
+        self.visit_expression(target);
+        self.type_accumulator = match &self.type_accumulator {
+            Type::List(element_type) => (**element_type).clone(),
+            Type::Unresolved => Type::Unresolved,
+            found_type => {
+                self.diagnostics.report(Diagnostic::not_indexable(found_type.clone(), target.span()));
+                Type::Unresolved
+            }
+        };
+    }
+
+    fn visit_conditional_expression(&mut self, condition: &Expression, then_expr: &Expression, else_expr: &Expression) {
+        self.visit_expression(condition);
+        if !Type::Bool.accepts(&self.type_accumulator) {
+            self.diagnostics.report(Diagnostic::expression_type_mismatch(Type::Bool, self.type_accumulator.clone(), condition.span()));
+        }
+
+        self.visit_expression(then_expr);
+        let then_type = self.type_accumulator.clone();
+        self.visit_expression(else_expr);
+        let else_type = self.type_accumulator.clone();
+
+        // Both branches must agree, the then branch setting the expected type
+        if !then_type.accepts(&else_type) {
+            self.diagnostics.report(Diagnostic::expression_type_mismatch(then_type.clone(), else_type.clone(), else_expr.span()));
+        }
+        self.type_accumulator = if then_type.is_resolved() { then_type } else { else_type };
+    }
+
+    fn visit_error_expression(&mut self) {
+        self.type_accumulator = Type::Unresolved;
+    }
+}
+
+// Evaluates conditions built only from literals, e.g. `true`, `not false` or `1 < 2`
+fn fold_boolean_constant(expression: &Expression) -> Option<bool> {
+    match expression {
+        Expression::Literal { value: Literal::Boolean(value), .. } => Some(*value),
+        Expression::Grouped(inner) => fold_boolean_constant(inner),
+        Expression::UnaryOperation { operator: UnaryOperator::Not, operand } => fold_boolean_constant(operand).map(|value| !value),
+        Expression::BinaryOperation { left, operator, right } => match operator {
+            BinaryOperator::And => Some(fold_boolean_constant(left)? && fold_boolean_constant(right)?),
+            BinaryOperator::Or => Some(fold_boolean_constant(left)? || fold_boolean_constant(right)?),
+            BinaryOperator::Equal => Some(fold_integer_constant(left)? == fold_integer_constant(right)?),
+            BinaryOperator::NotEqual => Some(fold_integer_constant(left)? != fold_integer_constant(right)?),
+            BinaryOperator::LessThan => Some(fold_integer_constant(left)? < fold_integer_constant(right)?),
+            BinaryOperator::GreaterThan => Some(fold_integer_constant(left)? > fold_integer_constant(right)?),
+            BinaryOperator::LessThanOrEqual => Some(fold_integer_constant(left)? <= fold_integer_constant(right)?),
+            BinaryOperator::GreaterThanOrEqual => Some(fold_integer_constant(left)? >= fold_integer_constant(right)?),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn fold_integer_constant(expression: &Expression) -> Option<i64> {
+    match expression {
+        Expression::Literal { value: Literal::Number(value), .. } => Some(*value),
+        Expression::Grouped(inner) => fold_integer_constant(inner),
+        _ => None,
+    }
+}
+
+// Structural equality ignoring source positions, which the derived PartialEq compares.
+// Function calls are never considered equal since each call may return something different.
+fn is_same_expression(left: &Expression, right: &Expression) -> bool {
+    match (left, right) {
+        (Expression::Grouped(left), right) | (right, Expression::Grouped(left)) => is_same_expression(left, right),
+        (Expression::Literal { value: left, .. }, Expression::Literal { value: right, .. }) => left == right,
+        (Expression::Variable(left), Expression::Variable(right)) => left.value == right.value,
+        (
+            Expression::BinaryOperation { left: left_lhs, operator: left_operator, right: left_rhs },
+            Expression::BinaryOperation { left: right_lhs, operator: right_operator, right: right_rhs },
+        ) => left_operator == right_operator && is_same_expression(left_lhs, right_lhs) && is_same_expression(left_rhs, right_rhs),
+        (
+            Expression::UnaryOperation { operator: left_operator, operand: left_operand },
+            Expression::UnaryOperation { operator: right_operator, operand: right_operand },
+        ) => left_operator == right_operator && is_same_expression(left_operand, right_operand),
+        (Expression::Index { target: left_target, index: left_index, .. }, Expression::Index { target: right_target, index: right_index, .. }) =>
+            is_same_expression(left_target, right_target) && is_same_expression(left_index, right_index),
+        (Expression::List { elements: left, .. }, Expression::List { elements: right, .. }) =>
+            left.len() == right.len() && left.iter().zip(right).all(|(left, right)| is_same_expression(left, right)),
+        _ => false,
+    }
+}
+
+fn ungrouped(expression: &Expression) -> &Expression {
+    match expression {
+        Expression::Grouped(inner) => ungrouped(inner),
+        expression => expression,
+    }
+}
+
+// Whether a `return` with a value can be reached in the statement, outside of nested function definitions
+fn returns_value(statement: &Statement) -> bool {
+    match statement {
+        Statement::ReturnStatement { expression, .. } => expression.is_some(),
+        Statement::BlockStatement { statements, .. } => statements.iter().any(returns_value),
+        Statement::IfStatement { if_then_branch, else_branch, .. } =>
+            returns_value(&if_then_branch.then_branch) || else_branch.as_deref().is_some_and(returns_value),
+        Statement::WhileStatement { body, .. }
+        | Statement::ForStatement { body, .. }
+        | Statement::RepeatStatement { body, .. } => returns_value(body),
+        Statement::WhenStatement { arms, default, .. } =>
+            arms.iter().any(|(_, body)| returns_value(body)) || default.as_deref().is_some_and(returns_value),
+        _ => false,
+    }
+}
+
+// Whether every way through the statement ends with a `return` of a value
+fn returns_on_every_path(statement: &Statement) -> bool {
+    match statement {
+        Statement::ReturnStatement { expression, .. } => expression.is_some(),
+        Statement::BlockStatement { statements, .. } => statements.iter().any(returns_on_every_path),
+        Statement::IfStatement { if_then_branch, else_branch, .. } =>
+            returns_on_every_path(&if_then_branch.then_branch) && else_branch.as_deref().is_some_and(returns_on_every_path),
+        Statement::WhenStatement { arms, default, .. } =>
+            arms.iter().all(|(_, body)| returns_on_every_path(body)) && default.as_deref().is_some_and(returns_on_every_path),
+        // `while true` only ends through a return
+        Statement::WhileStatement { condition, .. } => fold_boolean_constant(condition) == Some(true),
+        // The body of other loops may not run at all
+        _ => false,
+    }
+}
+
+fn references_variable(expression: &Expression) -> bool {
+    match expression {
+        Expression::Variable(_) => true,
+        Expression::Grouped(inner) => references_variable(inner),
+        Expression::BinaryOperation { left, right, .. } => references_variable(left) || references_variable(right),
+        Expression::UnaryOperation { operand, .. } => references_variable(operand),
+        Expression::Index { target, index, .. } => references_variable(target) || references_variable(index),
+        Expression::List { elements, .. } => elements.iter().any(references_variable),
+        Expression::Conditional { condition, then_expr, else_expr, .. } =>
+            references_variable(condition) || references_variable(then_expr) || references_variable(else_expr),
+        Expression::Literal { .. } | Expression::FunctionCall(_) | Expression::Error(_) => false,
+    }
+}
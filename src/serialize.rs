@@ -0,0 +1,186 @@
+use crate::{ast::{expression::{Expression, Literal, UnaryOperator}, statement::Statement, Ast}, lexer::{TextSpan, Token}, types::Type};
+
+// Every node is an object with a "type", its "span" and one field per child
+pub fn to_json(ast: &Ast) -> String {
+    let statements = ast.statements().iter().map(statement_to_json).collect::<Vec<_>>();
+    format!("{{\"type\":\"Program\",\"statements\":[{}]}}", statements.join(","))
+}
+
+fn statement_to_json(statement: &Statement) -> String {
+    let span = span_to_json(&statement.span());
+
+    match statement {
+        Statement::VariableDeclaration { name, type_annotation, value, .. } => format!(
+            "{{\"type\":\"VariableDeclaration\",\"span\":{},\"name\":{},\"type_annotation\":{},\"value\":{}}}",
+            span,
+            token_to_json(name),
+            type_to_json(type_annotation.as_ref()),
+            expression_to_json(value)
+        ),
+        Statement::ConstDeclaration { name, value, .. } => format!(
+            "{{\"type\":\"ConstDeclaration\",\"span\":{},\"name\":{},\"value\":{}}}",
+            span, token_to_json(name), expression_to_json(value)
+        ),
+        Statement::VariableAssignment { name, value, .. } => format!(
+            "{{\"type\":\"VariableAssignment\",\"span\":{},\"name\":{},\"value\":{}}}",
+            span, token_to_json(name), expression_to_json(value)
+        ),
+        Statement::SwapStatement { left, right, .. } => format!(
+            "{{\"type\":\"SwapStatement\",\"span\":{},\"left\":{},\"right\":{}}}",
+            span, token_to_json(left), token_to_json(right)
+        ),
+        Statement::IfStatement { if_then_branch, else_branch, .. } => format!(
+            "{{\"type\":\"IfStatement\",\"span\":{},\"condition\":{},\"then_branch\":{},\"else_branch\":{}}}",
+            span,
+            expression_to_json(&if_then_branch.condition),
+            statement_to_json(&if_then_branch.then_branch),
+            else_branch.as_ref().map_or("null".to_string(), |branch| statement_to_json(branch))
+        ),
+        Statement::BlockStatement { statements, .. } => format!(
+            "{{\"type\":\"BlockStatement\",\"span\":{},\"statements\":[{}]}}",
+            span, statements.iter().map(statement_to_json).collect::<Vec<_>>().join(",")
+        ),
+        Statement::WhileStatement { condition, body, .. } => format!(
+            "{{\"type\":\"WhileStatement\",\"span\":{},\"condition\":{},\"body\":{}}}",
+            span, expression_to_json(condition), statement_to_json(body)
+        ),
+        Statement::ForStatement { variable, retains_variable, start, end, step, body, .. } => format!(
+            "{{\"type\":\"ForStatement\",\"span\":{},\"variable\":{},\"retains_variable\":{},\"start\":{},\"end\":{},\"step\":{},\"body\":{}}}",
+            span,
+            token_to_json(variable),
+            retains_variable,
+            expression_to_json(start),
+            expression_to_json(end),
+            step.as_ref().map_or("null".to_string(), expression_to_json),
+            statement_to_json(body)
+        ),
+        Statement::RepeatStatement { count, body, .. } => format!(
+            "{{\"type\":\"RepeatStatement\",\"span\":{},\"count\":{},\"body\":{}}}",
+            span, expression_to_json(count), statement_to_json(body)
+        ),
+        Statement::WhenStatement { subject, arms, default, .. } => format!(
+            "{{\"type\":\"WhenStatement\",\"span\":{},\"subject\":{},\"arms\":[{}],\"default\":{}}}",
+            span,
+            expression_to_json(subject),
+            arms.iter()
+                .map(|(value, body)| format!("{{\"value\":{},\"body\":{}}}", expression_to_json(value), statement_to_json(body)))
+                .collect::<Vec<_>>()
+                .join(","),
+            default.as_deref().map_or("null".to_string(), statement_to_json)
+        ),
+        Statement::FunctionDefinition { name, arguments, return_type, body, .. } => format!(
+            "{{\"type\":\"FunctionDefinition\",\"span\":{},\"name\":{},\"arguments\":[{}],\"return_type\":{},\"body\":{}}}",
+            span,
+            token_to_json(name),
+            arguments.iter().map(|argument| format!(
+                "{{\"name\":{},\"type_annotation\":{}}}",
+                token_to_json(&argument.name),
+                type_to_json(argument.type_annotation.as_ref())
+            )).collect::<Vec<_>>().join(","),
+            type_to_json(return_type.as_ref()),
+            statement_to_json(body)
+        ),
+        Statement::FunctionCall(data) => format!(
+            "{{\"type\":\"FunctionCall\",\"span\":{},\"function_name\":{},\"arguments\":[{}]}}",
+            span,
+            token_to_json(&data.function_name),
+            data.arguments.iter().map(expression_to_json).collect::<Vec<_>>().join(",")
+        ),
+        Statement::ExpressionStatement(expression) => format!(
+            "{{\"type\":\"ExpressionStatement\",\"span\":{},\"expression\":{}}}",
+            span, expression_to_json(expression)
+        ),
+        Statement::ReturnStatement { expression, .. } => format!(
+            "{{\"type\":\"ReturnStatement\",\"span\":{},\"expression\":{}}}",
+            span, expression.as_ref().map_or("null".to_string(), expression_to_json)
+        ),
+    }
+}
+
+fn expression_to_json(expression: &Expression) -> String {
+    let span = span_to_json(&expression.span());
+
+    match expression {
+        Expression::Literal { value: Literal::Number(value), .. } => format!(
+            "{{\"type\":\"NumberLiteral\",\"span\":{},\"value\":{}}}",
+            span, value
+        ),
+        Expression::Literal { value: Literal::Boolean(value), .. } => format!(
+            "{{\"type\":\"BooleanLiteral\",\"span\":{},\"value\":{}}}",
+            span, value
+        ),
+        Expression::Variable(name) => format!(
+            "{{\"type\":\"Variable\",\"span\":{},\"name\":{}}}",
+            span, string_to_json(&name.value)
+        ),
+        Expression::BinaryOperation { left, operator, right } => format!(
+            "{{\"type\":\"BinaryOperation\",\"span\":{},\"operator\":{},\"left\":{},\"right\":{}}}",
+            span, string_to_json(&operator.to_string()), expression_to_json(left), expression_to_json(right)
+        ),
+        Expression::UnaryOperation { operator, operand } => {
+            let operator = match operator {
+                UnaryOperator::Negate => "-",
+                UnaryOperator::Not => "not",
+            };
+            format!(
+                "{{\"type\":\"UnaryOperation\",\"span\":{},\"operator\":{},\"operand\":{}}}",
+                span, string_to_json(operator), expression_to_json(operand)
+            )
+        }
+        Expression::Grouped(inner) => format!(
+            "{{\"type\":\"Grouped\",\"span\":{},\"expression\":{}}}",
+            span, expression_to_json(inner)
+        ),
+        Expression::FunctionCall(data) => format!(
+            "{{\"type\":\"FunctionCall\",\"span\":{},\"function_name\":{},\"arguments\":[{}]}}",
+            span,
+            token_to_json(&data.function_name),
+            data.arguments.iter().map(expression_to_json).collect::<Vec<_>>().join(",")
+        ),
+        Expression::List { elements, .. } => format!(
+            "{{\"type\":\"List\",\"span\":{},\"elements\":[{}]}}",
+            span, elements.iter().map(expression_to_json).collect::<Vec<_>>().join(",")
+        ),
+        Expression::Index { target, index, .. } => format!(
+            "{{\"type\":\"Index\",\"span\":{},\"target\":{},\"index\":{}}}",
+            span, expression_to_json(target), expression_to_json(index)
+        ),
+        Expression::Conditional { condition, then_expr, else_expr, .. } => format!(
+            "{{\"type\":\"Conditional\",\"span\":{},\"condition\":{},\"then\":{},\"else\":{}}}",
+            span, expression_to_json(condition), expression_to_json(then_expr), expression_to_json(else_expr)
+        ),
+        Expression::Error(_) => format!("{{\"type\":\"Error\",\"span\":{}}}", span),
+    }
+}
+
+fn token_to_json(token: &Token) -> String {
+    format!("{{\"value\":{},\"span\":{}}}", string_to_json(&token.value), span_to_json(&token.span()))
+}
+
+fn type_to_json(annotation: Option<&Type>) -> String {
+    annotation.map_or("null".to_string(), |annotation| string_to_json(&annotation.to_string()))
+}
+
+fn span_to_json(span: &TextSpan) -> String {
+    format!(
+        "{{\"start\":{{\"line\":{},\"column\":{}}},\"end\":{{\"line\":{},\"column\":{}}}}}",
+        span.start.line, span.start.column, span.end.line, span.end.column
+    )
+}
+
+fn string_to_json(value: &str) -> String {
+    let mut json = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
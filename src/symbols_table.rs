@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 
-use crate::types::Type;
+use crate::{lexer::{TextSpan, TokenPosition}, types::Type};
 
+#[derive(Clone)]
 pub struct SymbolsTable {
     scopes: Vec<Scope>,
     functions: HashMap<String, FunctionSymbol>,
@@ -15,8 +16,9 @@ impl SymbolsTable {
         }
     }
 
-    pub fn enter_scope(&mut self, current_scope_id: ScopeId) -> ScopeId {
-        let new_scope = Scope::new(current_scope_id);
+    // `span` is the source the scope covers, e.g. a block or a whole function definition
+    pub fn enter_scope(&mut self, current_scope_id: ScopeId, span: TextSpan) -> ScopeId {
+        let new_scope = Scope::new(current_scope_id, span);
         self.scopes.push(new_scope);
         ScopeId(self.scopes.len() - 1)
     }
@@ -39,50 +41,128 @@ impl SymbolsTable {
         scope.lookup(identifier)
     }
 
-    pub fn lookup_function(&self, identifier: &str) -> Option<&FunctionSymbol> {
-        self.functions.get(identifier)
-    }
-
-    pub fn lookup_variable(&self, identifier: &str, current_scope_id: ScopeId) -> Option<&VariableSymbol> {
+    pub fn lookup_variable_mut(&mut self, identifier: &str, current_scope_id: ScopeId) -> Option<&mut VariableSymbol> {
         let mut current_lookup_scope_id = Some(current_scope_id);
 
         while let Some(scope_id) = current_lookup_scope_id {
-            let scope = &self.scopes[scope_id.0];
-            if let Some(symbol) = scope.lookup(identifier) {
-                return Some(symbol);
+            if self.scopes[scope_id.0].variables.contains_key(identifier) {
+                return self.scopes[scope_id.0].variables.get_mut(identifier);
             }
-            current_lookup_scope_id = scope.parent;
+            current_lookup_scope_id = self.scopes[scope_id.0].parent;
         }
 
         None
     }
+
+    pub fn mark_variable_used(&mut self, identifier: &str, current_scope_id: ScopeId) {
+        if let Some(symbol) = self.lookup_variable_mut(identifier, current_scope_id) {
+            symbol.is_used = true;
+        }
+    }
+
+    // Local variables of the scope that were declared but never read, in declaration order
+    pub fn unused_variables(&self, scope_id: ScopeId) -> Vec<&VariableSymbol> {
+        let mut unused = self.scopes[scope_id.0]
+            .variables
+            .values()
+            .filter(|symbol| symbol.kind == VariableKind::Local && !symbol.is_used)
+            .collect::<Vec<_>>();
+
+        unused.sort_by_key(|symbol| (symbol.span.start.line, symbol.span.start.column));
+        unused
+    }
+
+    pub fn lookup_function(&self, identifier: &str) -> Option<&FunctionSymbol> {
+        self.functions.get(identifier)
+    }
+
+    pub fn lookup_function_mut(&mut self, identifier: &str) -> Option<&mut FunctionSymbol> {
+        self.functions.get_mut(identifier)
+    }
+
+    pub fn lookup_variable(&self, identifier: &str, current_scope_id: ScopeId) -> Option<&VariableSymbol> {
+        self.ancestor_scopes(current_scope_id)
+            .find_map(|scope_id| self.scopes[scope_id.0].lookup(identifier))
+    }
+
+    // Every defined function, in no particular order
+    pub fn all_functions(&self) -> impl Iterator<Item = &FunctionSymbol> {
+        self.functions.values()
+    }
+
+    // Variables declared directly in the scope, not in its parents, in no particular order
+    pub fn variables_in_scope(&self, scope_id: ScopeId) -> impl Iterator<Item = &VariableSymbol> {
+        self.scopes[scope_id.0].variables.values()
+    }
+
+    pub fn parent_scope(&self, scope_id: ScopeId) -> Option<ScopeId> {
+        self.scopes[scope_id.0].parent
+    }
+
+    // The scope itself, then each enclosing scope up to the global one
+    pub fn ancestor_scopes(&self, scope_id: ScopeId) -> impl Iterator<Item = ScopeId> + '_ {
+        std::iter::successors(Some(scope_id), |&scope_id| self.parent_scope(scope_id))
+    }
+
+    // The innermost scope covering the position, the global scope outside of any block.
+    // Nested scopes are created after their parents, so the last scope containing the position is the innermost.
+    pub fn scope_at(&self, position: &TokenPosition) -> ScopeId {
+        self.scopes
+            .iter()
+            .rposition(|scope| scope.span.as_ref().is_some_and(|span| span.contains(position)))
+            .map_or(ScopeId(0), ScopeId)
+    }
 }
 
+impl Default for SymbolsTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
+#[derive(Clone)]
 pub struct VariableSymbol {
     pub identifier: String,
     pub sym_type: Type,
+    pub kind: VariableKind,
+    pub span: TextSpan,
+    pub is_used: bool,
+    pub is_const: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableKind {
+    Local,
+    Parameter,
+    LoopVariable,
 }
 
+#[derive(Clone)]
 pub struct FunctionSymbol {
     pub identifier: String,
     pub parameters: Vec<String>,
+    pub parameter_types: Vec<Type>,
+    pub return_type: Type,
 }
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ScopeId(pub usize);
 
+#[derive(Clone)]
 struct Scope {
     variables: HashMap<String, VariableSymbol>,
     parent: Option<ScopeId>,
+    // None for the global scope, which covers the whole program
+    span: Option<TextSpan>,
 }
 
 impl Scope {
-    fn new(parent: ScopeId) -> Self {
+    fn new(parent: ScopeId, span: TextSpan) -> Self {
         Scope {
             variables: HashMap::new(),
             parent: Some(parent),
+            span: Some(span),
         }
     }
 
@@ -90,6 +170,7 @@ impl Scope {
         Scope {
             variables: HashMap::new(),
             parent: None,
+            span: None,
         }
     }
 
@@ -1,42 +1,131 @@
 use core::fmt;
+use std::collections::HashMap;
 
-use crate::ast::expression::BinaryOperator;
+use crate::{ast::expression::BinaryOperator, lexer::TextSpan};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Type {
     Int,
     Bool,
+    List(Box<Type>),
+    // What a function without a returned value evaluates to, it cannot be used as a value
+    Unit,
 
     Unresolved,
 }
 
-pub fn resolve_binary_operation_type(left: &Type, right: &Type, operator: &BinaryOperator) -> Type {
-    match (left, right, operator) {
-        (Type::Int, Type::Int, BinaryOperator::Add) => Type::Int,
-        (Type::Int, Type::Int, BinaryOperator::Subtract) => Type::Int,
-        (Type::Int, Type::Int, BinaryOperator::Multiply) => Type::Int,
-        (Type::Int, Type::Int, BinaryOperator::Divide) => Type::Int,
-        (Type::Int, Type::Int, BinaryOperator::Modulus) => Type::Int,
+// Resolved type of every expression, keyed by the expression's span.
+// A grouped or unary expression has the span of its operand, e.g. `(a + b)` and `a + b`, or `not done` and `done`.
+// Such a span holds the type of the outermost expression, which is recorded last.
+// In a program that resolves both types are the same, since grouping and unary operators keep their operand's type.
+pub struct TypeMap {
+    types: HashMap<TextSpan, Type>,
+}
 
+impl TypeMap {
+    pub fn new() -> Self {
+        TypeMap {
+            types: HashMap::new(),
+        }
+    }
 
-        (Type::Bool, Type::Bool, BinaryOperator::And) => Type::Bool,
-        (Type::Bool, Type::Bool, BinaryOperator::Or) => Type::Bool,
+    pub fn record(&mut self, span: TextSpan, expression_type: Type) {
+        self.types.insert(span, expression_type);
+    }
 
-        (_, _, BinaryOperator::Equal) => Type::Bool,
-        (_, _, BinaryOperator::NotEqual) => Type::Int,
-        (_, _, BinaryOperator::LessThan) => Type::Int,
-        (_, _, BinaryOperator::GreaterThan) => Type::Int,
-        (_, _, BinaryOperator::LessThanOrEqual) => Type::Int,
-        (_, _, BinaryOperator::GreaterThanOrEqual) => Type::Int,
-       _ => Type::Unresolved,
+    pub fn type_at(&self, span: &TextSpan) -> Option<&Type> {
+        self.types.get(span)
+    }
+
+    // Entries from `other` replace existing ones recorded at the same span
+    pub fn merge(&mut self, other: TypeMap) {
+        self.types.extend(other.types);
+    }
+}
+
+impl Default for TypeMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Type {
+    // An unresolved type is compatible with anything, so an error is only reported once at its source
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Unresolved, _) | (_, Type::Unresolved) => true,
+            (Type::List(element_type), Type::List(other_element_type)) => element_type.accepts(other_element_type),
+            _ => self == other,
+        }
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        match self {
+            Type::Unresolved => false,
+            Type::List(element_type) => element_type.is_resolved(),
+            _ => true,
+        }
+    }
+}
+
+// Type both operands of the operator must have, None for `==` and `!=` which compare any two values of the same type
+pub fn binary_operand_type(operator: &BinaryOperator) -> Option<Type> {
+    match operator {
+        BinaryOperator::Add
+        | BinaryOperator::Subtract
+        | BinaryOperator::Multiply
+        | BinaryOperator::Divide
+        | BinaryOperator::Modulus
+        | BinaryOperator::Power
+        | BinaryOperator::BitAnd
+        | BinaryOperator::BitOr
+        | BinaryOperator::BitXor
+        | BinaryOperator::ShiftLeft
+        | BinaryOperator::ShiftRight
+        | BinaryOperator::LessThan
+        | BinaryOperator::GreaterThan
+        | BinaryOperator::LessThanOrEqual
+        | BinaryOperator::GreaterThanOrEqual => Some(Type::Int),
+
+        BinaryOperator::And | BinaryOperator::Or => Some(Type::Bool),
+
+        BinaryOperator::Equal | BinaryOperator::NotEqual => None,
+    }
+}
+
+pub fn unary_operand_type(operator: &crate::ast::expression::UnaryOperator) -> Type {
+    match operator {
+        crate::ast::expression::UnaryOperator::Negate => Type::Int,
+        crate::ast::expression::UnaryOperator::Not => Type::Bool,
+    }
+}
+
+// An unresolved operand, e.g. a parameter without annotation, is taken to have the type the operator needs,
+// so only an operand of a known, wrong type is an error
+pub fn resolve_binary_operation_type(left: &Type, right: &Type, operator: &BinaryOperator) -> Type {
+    let Some(operand_type) = binary_operand_type(operator) else {
+        return if left.accepts(right) { Type::Bool } else { Type::Unresolved };
+    };
+
+    if !(operand_type.accepts(left) && operand_type.accepts(right)) {
+        return Type::Unresolved;
+    }
+    match operator {
+        BinaryOperator::LessThan
+        | BinaryOperator::GreaterThan
+        | BinaryOperator::LessThanOrEqual
+        | BinaryOperator::GreaterThanOrEqual => Type::Bool,
+        _ => operand_type,
     }
 }
 
 pub fn resolve_unary_operation_type(operand: &Type, operator: &crate::ast::expression::UnaryOperator) -> Type {
-    match (operand, operator) {
-        (Type::Int, crate::ast::expression::UnaryOperator::Negate) => Type::Int,
-        (Type::Bool, crate::ast::expression::UnaryOperator::Not) => Type::Bool,
-        _ => Type::Unresolved,
+    let operand_type = unary_operand_type(operator);
+
+    if operand_type.accepts(operand) {
+        operand_type
+    } else {
+        Type::Unresolved
     }
 }
 
@@ -45,6 +134,8 @@ impl fmt::Display for Type {
         match self {
             Type::Int => write!(f, "int"),
             Type::Bool => write!(f, "bool"),
+            Type::List(element_type) => write!(f, "list[{}]", element_type),
+            Type::Unit => write!(f, "unit"),
             Type::Unresolved => write!(f, "unresolved"),
         }
     }
@@ -10,9 +10,25 @@ impl AstDebugPrinter {
     }
 }
 
+impl Default for AstDebugPrinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AstExplorer for AstDebugPrinter {
-    fn visit_variable_declaration(&mut self, name: &crate::lexer::Token, value: &crate::ast::expression::Expression) {
-        println!("{}Variable Declaration: {}", "  ".repeat(self.indent_level), name.value);
+    fn visit_variable_declaration(&mut self, name: &crate::lexer::Token, type_annotation: Option<&crate::types::Type>, value: &crate::ast::expression::Expression) {
+        match type_annotation {
+            Some(annotation) => println!("{}Variable Declaration: {}: {}", "  ".repeat(self.indent_level), name.value, annotation),
+            None => println!("{}Variable Declaration: {}", "  ".repeat(self.indent_level), name.value),
+        }
+        self.indent_level += 1;
+        self.visit_expression(value);
+        self.indent_level -= 1;
+    }
+
+    fn visit_const_declaration(&mut self, name: &crate::lexer::Token, value: &crate::ast::expression::Expression) {
+        println!("{}Constant Declaration: {}", "  ".repeat(self.indent_level), name.value);
         self.indent_level += 1;
         self.visit_expression(value);
         self.indent_level -= 1;
@@ -53,6 +69,10 @@ impl AstExplorer for AstDebugPrinter {
         self.indent_level -= 1;
     }
     
+    fn visit_swap_statement(&mut self, left: &crate::lexer::Token, right: &crate::lexer::Token) {
+        println!("{}Swap: {} and {}", "  ".repeat(self.indent_level), left.value, right.value);
+    }
+
     fn visit_if_statement(&mut self, condition: &crate::ast::expression::Expression, then_branch: &crate::ast::statement::Statement, else_branch: Option<&crate::ast::statement::Statement>) {
         println!("{}If Statement:", "  ".repeat(self.indent_level));
         self.indent_level += 1;
@@ -62,6 +82,19 @@ impl AstExplorer for AstDebugPrinter {
         println!("{}Then Branch:", "  ".repeat(self.indent_level));
         self.visit_statement(then_branch);
         
+        // `elif` and `else if` chains are listed as sibling branches rather than nested if statements
+        let mut else_branch = else_branch;
+        while let Some(crate::ast::statement::Statement::IfStatement { if_then_branch, else_branch: next_branch, .. }) = else_branch {
+            println!("{}Else If Branch:", "  ".repeat(self.indent_level));
+            self.indent_level += 1;
+            println!("{}Condition:", "  ".repeat(self.indent_level));
+            self.visit_expression(&if_then_branch.condition);
+            println!("{}Then Branch:", "  ".repeat(self.indent_level));
+            self.visit_statement(&if_then_branch.then_branch);
+            self.indent_level -= 1;
+            else_branch = next_branch.as_deref();
+        }
+
         if let Some(else_branch) = else_branch {
             println!("{}Else Branch:", "  ".repeat(self.indent_level));
             self.visit_statement(else_branch);
@@ -96,10 +129,10 @@ impl AstExplorer for AstDebugPrinter {
         self.indent_level -= 1;
     }
     
-    fn visit_for_statement(&mut self, variable: &crate::lexer::Token, start: &crate::ast::expression::Expression, end: &crate::ast::expression::Expression, step: &Option<crate::ast::expression::Expression>, body: &crate::ast::statement::Statement) {
+    fn visit_for_statement(&mut self, variable: &crate::lexer::Token, retains_variable: bool, start: &crate::ast::expression::Expression, end: &crate::ast::expression::Expression, step: &Option<crate::ast::expression::Expression>, body: &crate::ast::statement::Statement) {
         println!("{}For Statement:", "  ".repeat(self.indent_level));
         self.indent_level += 1;
-        println!("{}Variable: {}", "  ".repeat(self.indent_level), variable.value);
+        println!("{}Variable: {}{}", "  ".repeat(self.indent_level), variable.value, if retains_variable { " (retained)" } else { "" });
         
         println!("{}Start:", "  ".repeat(self.indent_level));
         self.visit_expression(start);
@@ -118,18 +151,61 @@ impl AstExplorer for AstDebugPrinter {
         self.indent_level -= 1;
     }
     
-    fn visit_function_definition(&mut self, name: &crate::lexer::Token, arguments: &[crate::lexer::Token], body: &crate::ast::statement::Statement) {
+    fn visit_repeat_statement(&mut self, count: &crate::ast::expression::Expression, body: &crate::ast::statement::Statement) {
+        println!("{}Repeat Statement:", "  ".repeat(self.indent_level));
+        self.indent_level += 1;
+        println!("{}Count:", "  ".repeat(self.indent_level));
+        self.visit_expression(count);
+        
+        println!("{}Body:", "  ".repeat(self.indent_level));
+        self.visit_statement(body);
+        
+        self.indent_level -= 1;
+    }
+    
+    fn visit_when_statement(&mut self, subject: &crate::ast::expression::Expression, arms: &[(crate::ast::expression::Expression, crate::ast::statement::Statement)], default: Option<&crate::ast::statement::Statement>) {
+        println!("{}When Statement:", "  ".repeat(self.indent_level));
+        self.indent_level += 1;
+        println!("{}Subject:", "  ".repeat(self.indent_level));
+        self.visit_expression(subject);
+
+        for (value, body) in arms {
+            println!("{}Arm:", "  ".repeat(self.indent_level));
+            self.indent_level += 1;
+            println!("{}Value:", "  ".repeat(self.indent_level));
+            self.visit_expression(value);
+            println!("{}Body:", "  ".repeat(self.indent_level));
+            self.visit_statement(body);
+            self.indent_level -= 1;
+        }
+
+        if let Some(default) = default {
+            println!("{}Otherwise Branch:", "  ".repeat(self.indent_level));
+            self.visit_statement(default);
+        }
+        
+        self.indent_level -= 1;
+    }
+    
+    fn visit_function_definition(&mut self, name: &crate::lexer::Token, arguments: &[crate::ast::statement::Parameter], return_type: Option<&crate::types::Type>, body: &crate::ast::statement::Statement) {
         println!("{}Function Definition: {}", "  ".repeat(self.indent_level), name.value);
         self.indent_level += 1;
         
         if !arguments.is_empty() {
             println!("{}Arguments:", "  ".repeat(self.indent_level));
             for arg in arguments {
-                println!("{}- {}", "  ".repeat(self.indent_level + 1), arg.value);
+                match &arg.type_annotation {
+                    Some(annotation) => println!("{}- {}: {}", "  ".repeat(self.indent_level + 1), arg.name.value, annotation),
+                    None => println!("{}- {}", "  ".repeat(self.indent_level + 1), arg.name.value),
+                }
             }
         } else {
             println!("{}No Arguments", "  ".repeat(self.indent_level));
         }
+
+        if let Some(return_type) = return_type {
+            println!("{}Returns: {}", "  ".repeat(self.indent_level), return_type);
+        }
         
         println!("{}Body:", "  ".repeat(self.indent_level));
         self.visit_statement(body);
@@ -166,4 +242,60 @@ impl AstExplorer for AstDebugPrinter {
         
         self.indent_level -= 1;
     }
-}
\ No newline at end of file
+
+    fn visit_list_expression(&mut self, elements: &[crate::ast::expression::Expression]) {
+        println!("{}List: {} element(s)", "  ".repeat(self.indent_level), elements.len());
+        self.indent_level += 1;
+        for element in elements {
+            self.visit_expression(element);
+        }
+        self.indent_level -= 1;
+    }
+
+    fn visit_index_expression(&mut self, target: &crate::ast::expression::Expression, index: &crate::ast::expression::Expression) {
+        println!("{}Index:", "  ".repeat(self.indent_level));
+        self.indent_level += 1;
+        println!("{}Target:", "  ".repeat(self.indent_level));
+        self.visit_expression(target);
+        println!("{}Index:", "  ".repeat(self.indent_level));
+        self.visit_expression(index);
+        self.indent_level -= 1;
+    }
+
+    fn visit_conditional_expression(&mut self, condition: &crate::ast::expression::Expression, then_expr: &crate::ast::expression::Expression, else_expr: &crate::ast::expression::Expression) {
+        println!("{}Conditional:", "  ".repeat(self.indent_level));
+        self.indent_level += 1;
+        println!("{}Condition:", "  ".repeat(self.indent_level));
+        self.visit_expression(condition);
+        println!("{}Then:", "  ".repeat(self.indent_level));
+        self.visit_expression(then_expr);
+        println!("{}Else:", "  ".repeat(self.indent_level));
+        self.visit_expression(else_expr);
+        self.indent_level -= 1;
+    }
+
+    fn visit_error_expression(&mut self) {
+        println!("{}Error Expression", "  ".repeat(self.indent_level));
+    }
+}
+
+// Levenshtein distance, counted in characters
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b_chars.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current_row = vec![i + 1];
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution_cost = if a_char == *b_char { 0 } else { 1 };
+            current_row.push(
+                (previous_row[j] + substitution_cost)
+                    .min(previous_row[j + 1] + 1)
+                    .min(current_row[j] + 1)
+            );
+        }
+        previous_row = current_row;
+    }
+
+    previous_row[b_chars.len()]
+}
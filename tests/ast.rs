@@ -0,0 +1,132 @@
+use navacodelang::{ast::{expression::{BinaryOperator, Expression, UnaryOperator}, statement::{Parameter, Statement}, AstExplorer}, lexer::{Lexer, TextSpan, Token}, parser::Parser, types::Type};
+
+// Records the statement hooks while overriding `visit_statement`, like the resolver and the interpreter do
+#[derive(Default)]
+struct Tracer {
+    events: Vec<String>,
+    visited: usize,
+}
+
+fn kind(statement: &Statement) -> &'static str {
+    match statement {
+        Statement::VariableDeclaration { .. } => "let",
+        Statement::VariableAssignment { .. } => "set",
+        Statement::IfStatement { .. } => "if",
+        Statement::BlockStatement { .. } => "block",
+        _ => "other",
+    }
+}
+
+impl AstExplorer for Tracer {
+    fn visit_statement(&mut self, statement: &Statement) {
+        self.visited += 1;
+        self.visit_statement_impl(statement);
+    }
+
+    fn statement_on_enter(&mut self, statement: &Statement) {
+        self.events.push(format!("enter {}", kind(statement)));
+    }
+
+    fn statement_on_exit(&mut self, statement: &Statement) {
+        self.events.push(format!("exit {}", kind(statement)));
+    }
+
+    fn visit_variable_declaration(&mut self, _name: &Token, _type_annotation: Option<&Type>, _value: &Expression) {
+    }
+
+    fn visit_const_declaration(&mut self, _name: &Token, _value: &Expression) {
+    }
+
+    fn visit_variable_assignement(&mut self, _name: &Token, _value: &Expression) {
+    }
+
+    fn visit_swap_statement(&mut self, _left: &Token, _right: &Token) {
+    }
+
+    fn visit_if_statement(&mut self, _condition: &Expression, then_branch: &Statement, else_branch: Option<&Statement>) {
+        self.visit_statement(then_branch);
+        if let Some(else_branch) = else_branch {
+            self.visit_statement(else_branch);
+        }
+    }
+
+    fn visit_while_statement(&mut self, _condition: &Expression, body: &Statement) {
+        self.visit_statement(body);
+    }
+
+    fn visit_for_statement(&mut self, _variable: &Token, _retains_variable: bool, _start: &Expression, _end: &Expression, _step: &Option<Expression>, body: &Statement) {
+        self.visit_statement(body);
+    }
+
+    fn visit_repeat_statement(&mut self, _count: &Expression, body: &Statement) {
+        self.visit_statement(body);
+    }
+
+    fn visit_when_statement(&mut self, _subject: &Expression, arms: &[(Expression, Statement)], default: Option<&Statement>) {
+        for (_, body) in arms {
+            self.visit_statement(body);
+        }
+        if let Some(default) = default {
+            self.visit_statement(default);
+        }
+    }
+
+    fn visit_function_definition(&mut self, _name: &Token, _arguments: &[Parameter], _return_type: Option<&Type>, body: &Statement) {
+        self.visit_statement(body);
+    }
+
+    fn visit_function_call(&mut self, _function_name: &Token, _arguments: &[Expression]) {
+    }
+
+    fn visit_return_statement(&mut self, _span: TextSpan, _expression: &Option<Expression>) {
+    }
+
+    fn block_statement_on_enter(&mut self) {
+    }
+
+    fn block_statement_on_exit(&mut self) {
+    }
+
+    fn visit_number_expression(&mut self, _value: i64) {
+    }
+
+    fn visit_boolean_expression(&mut self, _value: bool) {
+    }
+
+    fn visit_variable_expression(&mut self, _name: &Token) {
+    }
+
+    fn visit_binary_operation(&mut self, _left: &Expression, _operator: &BinaryOperator, _right: &Expression) {
+    }
+
+    fn visit_unary_operation(&mut self, _operator: &UnaryOperator, _operand: &Expression) {
+    }
+
+    fn visit_list_expression(&mut self, _elements: &[Expression]) {
+    }
+
+    fn visit_index_expression(&mut self, _target: &Expression, _index: &Expression) {
+    }
+
+    fn visit_conditional_expression(&mut self, _condition: &Expression, _then_expr: &Expression, _else_expr: &Expression) {
+    }
+
+    fn visit_error_expression(&mut self) {
+    }
+}
+
+#[test]
+fn statement_hooks_fire_when_visit_statement_is_overridden() {
+    let ast = Parser::new(Lexer::new("let x be 1\nif x > 0 then\n    set x to 2\nend")).parse().unwrap();
+    let mut tracer = Tracer::default();
+
+    tracer.explore_ast(&ast);
+
+    assert_eq!(tracer.visited, 4);
+    assert_eq!(tracer.events, vec![
+        "enter let", "exit let",
+        "enter if",
+        "enter block", "enter set", "exit set", "exit block",
+        "exit if",
+    ]);
+}
@@ -0,0 +1,35 @@
+use navacodelang::{bytecode::{Bytecode, Codegen, Instruction}, lexer::Lexer, parser::Parser};
+
+fn generate(source: &str) -> Bytecode {
+    Codegen::generate(&Parser::new(Lexer::new(source)).parse().unwrap()).unwrap_or_else(|error| panic!("{}", error))
+}
+
+#[test]
+fn operands_are_pushed_before_their_operator() {
+    let bytecode = generate("1 + 2 * 3");
+
+    assert_eq!(bytecode.instructions, vec![
+        Instruction::PushInt(0),
+        Instruction::PushInt(1),
+        Instruction::PushInt(2),
+        Instruction::Mul,
+        Instruction::Add,
+        Instruction::Pop,
+    ]);
+    assert_eq!(bytecode.constants, vec![1, 2, 3]);
+}
+
+#[test]
+fn equal_constants_share_a_slot() {
+    let bytecode = generate("let x be 7\nset x to x + 7");
+
+    assert_eq!(bytecode.instructions, vec![
+        Instruction::PushInt(0),
+        Instruction::DeclareVar("x".to_string()),
+        Instruction::LoadVar("x".to_string()),
+        Instruction::PushInt(0),
+        Instruction::Add,
+        Instruction::StoreVar("x".to_string()),
+    ]);
+    assert_eq!(bytecode.constants, vec![7]);
+}
@@ -0,0 +1,43 @@
+use navacodelang::{call_graph::CallGraph, lexer::Lexer, parser::Parser};
+
+fn call_graph(source: &str) -> CallGraph {
+    CallGraph::build(&Parser::new(Lexer::new(source)).parse().unwrap())
+}
+
+#[test]
+fn factorial_calls_itself() {
+    let graph = call_graph("
+define function factorial with n as
+    if n <= 1 then
+        return (1)
+    end
+    return (n * factorial(n - 1))
+end
+
+let result be factorial(5)
+");
+
+    assert_eq!(graph.callees_of("factorial"), vec!["factorial"]);
+    assert_eq!(graph.callers_of("factorial"), vec!["factorial"]);
+    assert_eq!(graph.detect_recursion(), vec!["factorial"]);
+}
+
+#[test]
+fn functions_calling_each_other_are_both_recursive() {
+    let graph = call_graph("
+define function ping with n as
+    return (pong(n - 1))
+end
+
+define function pong with n as
+    return (ping(n - 1))
+end
+
+define function leaf with n as
+    return (sign(n))
+end
+");
+
+    assert_eq!(graph.detect_recursion(), vec!["ping", "pong"]);
+    assert_eq!(graph.callees_of("leaf"), vec!["sign"]);
+}
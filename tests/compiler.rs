@@ -0,0 +1,42 @@
+use std::io::Cursor;
+
+use navacodelang::{compiler::{Compiler, SourceCode}, interpreter::{Interpreter, RuntimeValue}};
+
+#[test]
+fn line_is_numbered_from_one_without_its_line_break() {
+    let source_code = SourceCode::from_string("let x be 1\r\nlet y be 2\nlet z be 3".to_string());
+
+    assert_eq!(source_code.line(1), Some("let x be 1"));
+    assert_eq!(source_code.line(2), Some("let y be 2"));
+    assert_eq!(source_code.line(3), Some("let z be 3"));
+}
+
+#[test]
+fn line_zero_and_lines_past_the_end_do_not_exist() {
+    let source_code = SourceCode::from_string("let x be 1\nlet y be 2\n".to_string());
+
+    assert_eq!(source_code.line(0), None);
+    assert_eq!(source_code.line(3), None);
+}
+
+#[test]
+fn appended_cell_can_call_a_function_defined_in_an_earlier_one() {
+    let mut compilation_unit = Compiler::new()
+        .compile(&SourceCode::from_string("define function double with x as\n    return (x * 2)\nend".to_string()))
+        .unwrap_or_else(|diagnostics| panic!("the first cell should compile:\n{}", diagnostics));
+
+    let diagnostics = compilation_unit.append(&SourceCode::from_string("let answer be double(21)".to_string()));
+    assert!(!diagnostics.has_errors(), "{}", diagnostics);
+
+    let mut interpreter = Interpreter::new();
+    interpreter.run_program(&compilation_unit.ast);
+    assert_eq!(interpreter.globals().get("answer"), Some(&RuntimeValue::Number(42)));
+}
+
+#[test]
+fn source_code_is_read_from_a_reader() {
+    let source_code = SourceCode::from_reader(Cursor::new("let x be 1\nlet y be x")).unwrap();
+
+    assert_eq!(source_code.as_str(), "let x be 1\nlet y be x");
+    assert_eq!(source_code.line(2), Some("let y be x"));
+}
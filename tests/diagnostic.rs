@@ -0,0 +1,28 @@
+use navacodelang::{diagnostic::{Diagnostic, Diagnostics}, lexer::{TextSpan, TokenPosition}};
+
+fn span_at(line: usize, column: usize) -> TextSpan {
+    TextSpan { start: TokenPosition { line, column }, end: TokenPosition { line, column: column + 1 } }
+}
+
+#[test]
+fn merged_diagnostics_are_sorted_by_position() {
+    let mut diagnostics = Diagnostics::new();
+    diagnostics.report(Diagnostic::zero_step(span_at(3, 1)));
+    diagnostics.report(Diagnostic::return_outside_function(span_at(1, 9)));
+    let mut warnings = Diagnostics::new();
+    warnings.report(Diagnostic::unused_variable("x".to_string(), span_at(2, 5)));
+    warnings.report(Diagnostic::unused_variable("y".to_string(), span_at(1, 5)));
+    warnings.report(Diagnostic::unreachable_code(span_at(3, 1)));
+
+    diagnostics.merge(warnings);
+    diagnostics.sort_by_position();
+
+    // Diagnostics at the same position keep the order they were reported in
+    assert_eq!(diagnostics.to_string().lines().collect::<Vec<_>>(), vec![
+        "WARNING[W0003]: at 1:5: Variable 'y' is declared but never used",
+        "ERROR[E0017]: at 1:9: Return statement outside of function",
+        "WARNING[W0003]: at 2:5: Variable 'x' is declared but never used",
+        "WARNING[W0007]: at 3:1: A for loop with a step of 0 never ends",
+        "WARNING[W0005]: at 3:1: Unreachable code after return statement",
+    ]);
+}
@@ -0,0 +1,328 @@
+use std::{cell::RefCell, rc::Rc};
+
+use navacodelang::{compiler::{Compiler, CompilerOptions, DivisionMode, IntWidth, OverflowMode, SourceCode}, interpreter::{Interpreter, RuntimeValue}, lexer::Lexer, parser::Parser};
+
+fn run(source: &str) -> Interpreter {
+    run_with(Interpreter::new(), source)
+}
+
+fn run_with(mut interpreter: Interpreter, source: &str) -> Interpreter {
+    let compilation_unit = Compiler::new()
+        .compile(&SourceCode::from_string(source.to_string()))
+        .unwrap_or_else(|diagnostics| panic!("the program should compile:\n{}", diagnostics));
+    interpreter.run_program(&compilation_unit.ast);
+    interpreter
+}
+
+// Runs the program without resolving it, so errors the resolver would reject reach the interpreter
+fn run_unresolved(source: &str) -> Interpreter {
+    let ast = Parser::new(Lexer::new(source)).parse().unwrap_or_else(|diagnostics| panic!("the program should parse:\n{}", diagnostics));
+    let mut interpreter = Interpreter::new();
+    interpreter.run(&ast);
+    interpreter
+}
+
+#[test]
+fn variable_change_callback_fires_on_declaration_and_assignment() {
+    let changes = Rc::new(RefCell::new(Vec::new()));
+    let mut interpreter = Interpreter::new();
+    let recorded = Rc::clone(&changes);
+    interpreter.on_variable_change(Box::new(move |name, value| recorded.borrow_mut().push((name.to_string(), value.clone()))));
+
+    run_with(interpreter, "let x be 1\nset x to 2");
+
+    assert_eq!(*changes.borrow(), vec![
+        ("x".to_string(), RuntimeValue::Number(1)),
+        ("x".to_string(), RuntimeValue::Number(2)),
+    ]);
+}
+
+#[test]
+fn retained_loop_variable_keeps_the_first_value_past_the_end() {
+    let interpreter = run("let total be 0\nfor let i from 1 to 3 do\n    set total to total + i\nend\nlet after be i");
+
+    assert_eq!(interpreter.globals().get("after"), Some(&RuntimeValue::Number(4)));
+}
+
+#[test]
+fn retained_loop_variable_keeps_the_start_value_when_the_loop_never_runs() {
+    let interpreter = run("for let k from 1 to 0 do\nend\nlet after be k");
+
+    assert_eq!(interpreter.globals().get("after"), Some(&RuntimeValue::Number(1)));
+}
+
+#[test]
+fn len_counts_the_elements_of_a_list() {
+    let interpreter = run("let count be len([4, 5, 6])");
+
+    assert_eq!(interpreter.globals().get("count"), Some(&RuntimeValue::Number(3)));
+}
+
+const FACTORIAL: &str = "
+define function factorial with n as
+    if n <= 1 then
+        return (1)
+    end
+    return (n * factorial(n - 1))
+end
+";
+
+#[test]
+fn function_call_results_flow_into_comparisons() {
+    let source = format!("{}
+define function is_even with n as
+    return (n % 2 == 0)
+end
+
+define function sum_to_n with n as
+    return (n * (n + 1) / 2)
+end
+
+let r be is_even(factorial(4))
+let bigger be factorial(3) > sum_to_n(2)
+", FACTORIAL);
+    let interpreter = run(&source);
+
+    assert_eq!(interpreter.globals().get("r"), Some(&RuntimeValue::Bool(true)));
+    assert_eq!(interpreter.globals().get("bigger"), Some(&RuntimeValue::Bool(true)));
+}
+
+#[test]
+#[should_panic(expected = "integer overflow")]
+fn factorial_of_25_reports_overflow() {
+    run(&format!("{}\nlet result be factorial(25)", FACTORIAL));
+}
+
+#[test]
+fn factorial_of_20_fits_in_64_bits() {
+    let interpreter = run(&format!("{}\nlet result be factorial(20)", FACTORIAL));
+
+    assert_eq!(interpreter.globals().get("result"), Some(&RuntimeValue::Number(2432902008176640000)));
+}
+
+fn eight_bit_options(overflow: OverflowMode) -> CompilerOptions {
+    CompilerOptions { int_width: IntWidth::Bits8, overflow, ..CompilerOptions::default() }
+}
+
+#[test]
+fn eight_bit_wrapping_addition_wraps_around() {
+    let interpreter = run_with(Interpreter::with_options(eight_bit_options(OverflowMode::Wrap)), "let sum be 200 + 100");
+
+    assert_eq!(interpreter.globals().get("sum"), Some(&RuntimeValue::Number(44)));
+}
+
+#[test]
+#[should_panic(expected = "integer overflow")]
+fn eight_bit_checked_addition_reports_overflow() {
+    run_with(Interpreter::with_options(eight_bit_options(OverflowMode::Error)), "let sum be 200 + 100");
+}
+
+#[test]
+fn for_loop_up_to_the_largest_integer_ends() {
+    for overflow in [OverflowMode::Wrap, OverflowMode::Error] {
+        let source = "let count be 0\nfor let i from 120 to 127 do\n    set count to count + 1\nend\nlet last be i";
+        let interpreter = run_with(Interpreter::with_options(eight_bit_options(overflow)), source);
+
+        assert_eq!(interpreter.globals().get("count"), Some(&RuntimeValue::Number(8)));
+        // 128 does not fit in 8 bits, so the variable keeps the last value instead of the first one past `end`
+        assert_eq!(interpreter.globals().get("last"), Some(&RuntimeValue::Number(127)));
+    }
+}
+
+#[test]
+fn for_loop_counts_up_and_down() {
+    let interpreter = run("let up be 0\nfor i from 1 to 4 do\n    set up to up * 10 + i\nend\nlet down be 0\nfor i from 4 to 1 step -1 do\n    set down to down * 10 + i\nend");
+
+    assert_eq!(interpreter.globals().get("up"), Some(&RuntimeValue::Number(1234)));
+    assert_eq!(interpreter.globals().get("down"), Some(&RuntimeValue::Number(4321)));
+}
+
+#[test]
+fn power_is_right_associative() {
+    let interpreter = run("let power be 2 ** 3 ** 2");
+
+    assert_eq!(interpreter.globals().get("power"), Some(&RuntimeValue::Number(512)));
+}
+
+#[test]
+#[should_panic(expected = "loop exceeded the limit of 1000 iterations")]
+fn endless_loop_stops_at_the_iteration_limit() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_max_loop_iterations(Some(1000));
+
+    run_with(interpreter, "while true do\nend");
+}
+
+#[test]
+fn main_can_read_and_update_top_level_variables() {
+    let interpreter = run("let limit be 10\nlet result be 0\n\ndefine function main as\n    set result to limit * 2\nend");
+
+    assert_eq!(interpreter.globals().get("result"), Some(&RuntimeValue::Number(20)));
+}
+
+#[test]
+fn list_literals_are_indexed_from_zero() {
+    let interpreter = run("let xs be [10, 20, 30]\nlet first be xs[0]\nlet last be xs[2]");
+
+    assert_eq!(interpreter.globals().get("xs"), Some(&RuntimeValue::List(vec![
+        RuntimeValue::Number(10),
+        RuntimeValue::Number(20),
+        RuntimeValue::Number(30),
+    ])));
+    assert_eq!(interpreter.globals().get("first"), Some(&RuntimeValue::Number(10)));
+    assert_eq!(interpreter.globals().get("last"), Some(&RuntimeValue::Number(30)));
+}
+
+#[test]
+#[should_panic(expected = "index 3 is out of bounds for a list of length 3")]
+fn index_past_the_end_is_reported() {
+    run("let xs be [10, 20, 30]\nlet x be xs[3]");
+}
+
+#[test]
+fn booleans_can_be_compared_for_inequality() {
+    let interpreter = run("let different be true != false\nlet same be true != true");
+
+    assert_eq!(interpreter.globals().get("different"), Some(&RuntimeValue::Bool(true)));
+    assert_eq!(interpreter.globals().get("same"), Some(&RuntimeValue::Bool(false)));
+}
+
+#[test]
+#[should_panic(expected = "at 1:4: condition must be a boolean")]
+fn condition_that_is_not_a_boolean_reports_its_span() {
+    run_unresolved("if 1 then\nend");
+}
+
+#[test]
+fn runtime_error_carries_the_span_of_the_failing_expression() {
+    let compilation_unit = Compiler::new()
+        .compile(&SourceCode::from_string("let x be 1\nlet y be x / 0\nlet z be 3".to_string()))
+        .unwrap();
+    let mut interpreter = Interpreter::new();
+
+    let error = interpreter.try_run_program(&compilation_unit.ast).unwrap_err();
+
+    assert_eq!(error.span().map(|span| (span.start.line, span.start.column)), Some((2, 10)));
+    assert_eq!(error.to_string(), "at 2:10: division by zero");
+    // The run stops at the error
+    assert_eq!(interpreter.globals().get("y"), None);
+    assert_eq!(interpreter.globals().get("z"), None);
+}
+
+#[test]
+#[should_panic(expected = "expected 2 arguments, found 1")]
+fn calling_a_function_with_too_few_arguments_is_reported() {
+    run_unresolved("define function add with a, b as\n    return (a + b)\nend\nlet sum be add(1)");
+}
+
+#[test]
+#[should_panic(expected = "function 'missing' not found")]
+fn calling_an_undefined_function_is_reported() {
+    run_unresolved("let x be missing(1)");
+}
+
+#[test]
+fn clamp_limits_the_value_to_the_range() {
+    let interpreter = run("let below be clamp(-5, 0, 10)\nlet within be clamp(4, 0, 10)\nlet above be clamp(15, 0, 10)");
+
+    assert_eq!(interpreter.globals().get("below"), Some(&RuntimeValue::Number(0)));
+    assert_eq!(interpreter.globals().get("within"), Some(&RuntimeValue::Number(4)));
+    assert_eq!(interpreter.globals().get("above"), Some(&RuntimeValue::Number(10)));
+}
+
+#[test]
+#[should_panic(expected = "clamp range is empty, lower bound 10 is greater than upper bound 0")]
+fn clamp_with_an_empty_range_is_reported() {
+    run("let x be clamp(5, 10, 0)");
+}
+
+#[test]
+fn sign_of_negative_zero_and_positive_numbers() {
+    let interpreter = run("let negative be sign(-42)\nlet zero be sign(0)\nlet positive be sign(7)");
+
+    assert_eq!(interpreter.globals().get("negative"), Some(&RuntimeValue::Number(-1)));
+    assert_eq!(interpreter.globals().get("zero"), Some(&RuntimeValue::Number(0)));
+    assert_eq!(interpreter.globals().get("positive"), Some(&RuntimeValue::Number(1)));
+}
+
+fn divide_negative_seven_by_two(division: DivisionMode) -> Interpreter {
+    let options = CompilerOptions { division, ..CompilerOptions::default() };
+    run_with(Interpreter::with_options(options), "let quotient be (-7) / 2\nlet remainder be (-7) % 2")
+}
+
+#[test]
+fn truncating_division_rounds_toward_zero() {
+    let interpreter = divide_negative_seven_by_two(DivisionMode::Truncate);
+
+    assert_eq!(interpreter.globals().get("quotient"), Some(&RuntimeValue::Number(-3)));
+    assert_eq!(interpreter.globals().get("remainder"), Some(&RuntimeValue::Number(-1)));
+}
+
+#[test]
+fn floor_division_rounds_toward_negative_infinity() {
+    let interpreter = divide_negative_seven_by_two(DivisionMode::Floor);
+
+    assert_eq!(interpreter.globals().get("quotient"), Some(&RuntimeValue::Number(-4)));
+    assert_eq!(interpreter.globals().get("remainder"), Some(&RuntimeValue::Number(1)));
+}
+
+#[test]
+fn declaration_with_only_a_type_starts_at_its_zero_value() {
+    let interpreter = run("let count be int\nlet done be bool\nlet is_zero be count == 0");
+
+    assert_eq!(interpreter.globals().get("count"), Some(&RuntimeValue::Number(0)));
+    assert_eq!(interpreter.globals().get("done"), Some(&RuntimeValue::Bool(false)));
+    assert_eq!(interpreter.globals().get("is_zero"), Some(&RuntimeValue::Bool(true)));
+}
+
+#[test]
+fn when_runs_the_first_matching_arm_or_the_default() {
+    let when = "when day\n    is 1 then\n        set kind to 10\n    is 2 then\n        set kind to 20\n    is 2 then\n        set kind to 30\n    otherwise\n        set kind to 0\nend";
+
+    for (day, kind) in [(1, 10), (2, 20), (5, 0)] {
+        let interpreter = run(&format!("let day be {}\nlet kind be -1\n{}", day, when));
+
+        assert_eq!(interpreter.globals().get("kind"), Some(&RuntimeValue::Number(kind)), "day {}", day);
+    }
+}
+
+#[test]
+fn conditional_expression_evaluates_the_chosen_branch_only() {
+    // The other branch would divide by zero
+    let interpreter = run("let a be 3\nlet b be 0\nlet bigger be if a > b then a else a / b\nlet smaller be (if a < b then a / b else b) * 2");
+
+    assert_eq!(interpreter.globals().get("bigger"), Some(&RuntimeValue::Number(3)));
+    assert_eq!(interpreter.globals().get("smaller"), Some(&RuntimeValue::Number(0)));
+}
+
+#[test]
+fn trace_fires_once_per_statement() {
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let recorded = Rc::clone(&lines);
+    let interpreter = Interpreter::with_trace(Box::new(move |statement| recorded.borrow_mut().push(statement.span().start.line)));
+
+    run_with(interpreter, "let x be 1\nset x to x + 1\nlet y be x * 2");
+
+    assert_eq!(*lines.borrow(), vec![1, 2, 3]);
+}
+
+#[test]
+fn state_dump_is_the_same_on_every_run() {
+    let source = "let zeta be 3\nlet alpha be true\nlet mid be [1, 2]";
+    let first = run(source).dump_state(true);
+
+    for _ in 0..5 {
+        assert_eq!(run(source).dump_state(true), first);
+    }
+    assert_eq!(first, "Current Variables:\nscope 0:\n  alpha: true\n  mid: [1, 2]\n  zeta: 3\n");
+}
+
+#[test]
+fn globals_are_listed_by_name_whatever_the_declaration_order() {
+    let forward = run("let a be 1\nlet b be 2\nlet c be 3");
+    let backward = run("let c be 3\nlet b be 2\nlet a be 1");
+
+    let names = |interpreter: &Interpreter| interpreter.globals().keys().cloned().collect::<Vec<_>>();
+    assert_eq!(names(&forward), vec!["a", "b", "c"]);
+    assert_eq!(names(&backward), names(&forward));
+}
@@ -0,0 +1,92 @@
+use navacodelang::lexer::{tokens_from_string, tokens_to_string, Lexer, Token, TokenKind};
+
+fn lex(source: &str) -> Vec<Token> {
+    Lexer::new(source).collect()
+}
+
+// Kind, text, line and column of each token
+fn positioned_tokens(source: &str) -> Vec<(TokenKind, String, usize, usize)> {
+    lex(source)
+        .into_iter()
+        .map(|token| (token.kind, token.value, token.position.line, token.position.column))
+        .collect()
+}
+
+#[test]
+fn token_stream_of_the_sample_program_round_trips() {
+    let source = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/testing.nvc")).unwrap();
+    let tokens = lex(&source);
+
+    assert_eq!(tokens_from_string(&tokens_to_string(&tokens)), Ok(tokens));
+}
+
+#[test]
+fn malformed_replay_line_reports_its_line_number() {
+    let mut serialized = tokens_to_string(&lex("let x be 1"));
+    serialized.push_str("garbage\n");
+
+    let error = tokens_from_string(&serialized).unwrap_err();
+    assert_eq!(error.line, 6);
+}
+
+#[test]
+fn token_after_a_partial_operator_match_keeps_its_position() {
+    assert_eq!(positioned_tokens("x <y"), vec![
+        (TokenKind::Identifier, "x".to_string(), 1, 1),
+        (TokenKind::LessThan, "<".to_string(), 1, 3),
+        (TokenKind::Identifier, "y".to_string(), 1, 4),
+        (TokenKind::EndOfFile, "".to_string(), 1, 5),
+    ]);
+}
+
+#[test]
+fn run_of_unknown_characters_is_one_token_spanning_all_of_them() {
+    let tokens = lex("let $$$ be 1");
+
+    assert_eq!((tokens[1].kind, tokens[1].value.as_str()), (TokenKind::Unknown, "$$$"));
+    let span = tokens[1].span();
+    assert_eq!((span.start.column, span.end.column), (5, 8));
+}
+
+#[test]
+fn identifier_with_a_non_ascii_letter_is_one_token() {
+    assert_eq!(positioned_tokens("let café be 1"), vec![
+        (TokenKind::LetKeyword, "let".to_string(), 1, 1),
+        (TokenKind::Identifier, "café".to_string(), 1, 5),
+        (TokenKind::BeKeyword, "be".to_string(), 1, 10),
+        (TokenKind::Number, "1".to_string(), 1, 13),
+        (TokenKind::EndOfFile, "".to_string(), 1, 14),
+    ]);
+}
+
+#[test]
+fn semicolon_separates_statements_on_one_line() {
+    let kinds = lex("let a be 1; let b be 2").into_iter().map(|token| token.kind).collect::<Vec<_>>();
+
+    assert_eq!(kinds, vec![
+        TokenKind::LetKeyword, TokenKind::Identifier, TokenKind::BeKeyword, TokenKind::Number,
+        TokenKind::Semicolon,
+        TokenKind::LetKeyword, TokenKind::Identifier, TokenKind::BeKeyword, TokenKind::Number,
+        TokenKind::EndOfFile,
+    ]);
+}
+
+#[test]
+fn keyword_span_covers_its_characters() {
+    let tokens = lex("define function f as\nend");
+
+    assert_eq!(tokens[0].kind, TokenKind::DefineKeyword);
+    // `end` is exclusive, so `define` covers columns 1 to 6
+    let span = tokens[0].span();
+    assert_eq!((span.start.line, span.start.column, span.end.line, span.end.column), (1, 1, 1, 7));
+}
+
+#[test]
+fn end_of_file_token_is_empty_and_just_past_the_last_character() {
+    let tokens = lex("let x be 1\nlet y be 22");
+
+    let end_of_file = tokens.last().unwrap();
+    assert_eq!(end_of_file.kind, TokenKind::EndOfFile);
+    assert_eq!(end_of_file.value, "");
+    assert_eq!((end_of_file.position.line, end_of_file.position.column), (2, 12));
+}
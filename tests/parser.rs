@@ -0,0 +1,194 @@
+use navacodelang::{ast::{expression::{Expression, Literal, UnaryOperator}, statement::Statement, Ast}, diagnostic::Diagnostics, lexer::Lexer, parser::Parser};
+
+fn parse(source: &str) -> Result<Ast, Diagnostics> {
+    Parser::new(Lexer::new(source)).parse()
+}
+
+fn error_codes(source: &str) -> Vec<&'static str> {
+    let diagnostics = parse(source).err().expect("the program should not parse");
+    diagnostics.diagnostics.iter().filter(|d| d.is_error()).map(|d| d.code()).collect()
+}
+
+fn declared_value(ast: &Ast) -> &Expression {
+    match ast.get(0) {
+        Some(Statement::VariableDeclaration { value, .. }) => value,
+        statement => panic!("expected a variable declaration, found {:?}", statement),
+    }
+}
+
+// Writes every binary operation in parentheses, showing how the parser grouped the operands
+fn parenthesize(expression: &Expression) -> String {
+    match expression {
+        Expression::Literal { value: Literal::Number(value), .. } => value.to_string(),
+        Expression::Variable(name) => name.value.clone(),
+        Expression::Grouped(inner) => parenthesize(inner),
+        Expression::BinaryOperation { left, operator, right } =>
+            format!("({} {} {})", parenthesize(left), operator, parenthesize(right)),
+        Expression::UnaryOperation { operator: UnaryOperator::Not, operand } => format!("(not {})", parenthesize(operand)),
+        Expression::UnaryOperation { operator: UnaryOperator::Negate, operand } => format!("(-{})", parenthesize(operand)),
+        Expression::Conditional { condition, then_expr, else_expr, .. } =>
+            format!("(if {} then {} else {})", parenthesize(condition), parenthesize(then_expr), parenthesize(else_expr)),
+        expression => panic!("unexpected expression {:?}", expression),
+    }
+}
+
+fn parenthesized_value(source: &str) -> String {
+    parenthesize(declared_value(&parse(source).unwrap()))
+}
+
+#[test]
+fn negative_number_is_folded_into_a_literal() {
+    let ast = parse("let x be -5").unwrap();
+
+    assert!(matches!(declared_value(&ast), Expression::Literal { value: Literal::Number(-5), .. }));
+}
+
+#[test]
+fn smallest_integer_literal_is_folded() {
+    let ast = parse("let x be -9223372036854775808").unwrap();
+
+    assert!(matches!(declared_value(&ast), Expression::Literal { value: Literal::Number(i64::MIN), .. }));
+}
+
+#[test]
+fn out_of_range_negative_literal_is_reported() {
+    assert_eq!(error_codes("let c be -99999999999999999999"), vec!["E0033"]);
+}
+
+#[test]
+fn digit_separators_are_ignored() {
+    let ast = parse("let x be 1_000").unwrap();
+
+    assert!(matches!(declared_value(&ast), Expression::Literal { value: Literal::Number(1000), .. }));
+}
+
+#[test]
+fn consecutive_digit_separators_are_reported() {
+    assert_eq!(error_codes("let x be 1__0"), vec!["E0030"]);
+}
+
+#[test]
+fn out_of_range_literal_is_reported() {
+    assert_eq!(error_codes("let c be 99999999999999999999"), vec!["E0033"]);
+}
+
+#[test]
+fn nested_assignment_is_reported() {
+    assert_eq!(error_codes("let a be set b to 5"), vec!["E0009"]);
+}
+
+#[test]
+fn assignment_on_the_next_line_is_not_taken_as_the_missing_value() {
+    // The missing value is reported at `set`, which then still parses as the next statement
+    assert_eq!(error_codes("let x be\nset y to 2"), vec!["E0001"]);
+}
+
+#[test]
+fn bitwise_operators_bind_between_comparisons_and_arithmetic() {
+    assert_eq!(parenthesized_value("let x be 1 bor 2 bxor 3 band 4 shl 5 + 6"), "(1 bor (2 bxor (3 band (4 shl (5 + 6)))))");
+    assert_eq!(parenthesized_value("let x be a band b == c"), "((a band b) == c)");
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    assert_eq!(parenthesized_value("let x be a or b and c"), "(a or (b and c))");
+    assert_eq!(parenthesized_value("let x be a and b or c"), "((a and b) or c)");
+}
+
+#[test]
+fn not_applies_to_its_operand_only() {
+    assert_eq!(parenthesized_value("let x be not a and b"), "((not a) and b)");
+}
+
+#[test]
+fn power_groups_from_the_right() {
+    assert_eq!(parenthesized_value("let x be 2 ** 3 ** 2"), "(2 ** (3 ** 2))");
+    assert_eq!(parenthesized_value("let x be 2 * 3 ** 2"), "(2 * (3 ** 2))");
+}
+
+#[test]
+fn grouped_expression_at_statement_start_is_an_expression_statement() {
+    let ast = parse("(1 + 2) * 3").unwrap();
+
+    match ast.get(0) {
+        Some(Statement::ExpressionStatement(expression)) => assert_eq!(parenthesize(expression), "((1 + 2) * 3)"),
+        statement => panic!("expected an expression statement, found {:?}", statement),
+    }
+}
+
+#[test]
+fn parenthesis_on_the_next_line_does_not_call_the_previous_name() {
+    let ast = parse("let a be b\n(1 + 2) * 3").unwrap();
+
+    assert_eq!(ast.len(), 2);
+    assert_eq!(parenthesize(declared_value(&ast)), "b");
+    assert!(matches!(ast.get(1), Some(Statement::ExpressionStatement(_))));
+}
+
+#[test]
+fn declaration_with_only_a_type_starts_at_its_zero_value() {
+    let ast = parse("let count be int").unwrap();
+    assert!(matches!(declared_value(&ast), Expression::Literal { value: Literal::Number(0), .. }));
+
+    let ast = parse("let done be bool").unwrap();
+    assert!(matches!(declared_value(&ast), Expression::Literal { value: Literal::Boolean(false), .. }));
+}
+
+const WHEN_WITH_THREE_ARMS: &str = "when day\n    is 1 then\n        set kind to 10\n    is 2 then\n        set kind to 20\n    is 3 then\n        set kind to 30\n    otherwise\n        set kind to 0\nend";
+
+#[test]
+fn when_statement_keeps_its_arms_in_order_and_the_default() {
+    let ast = parse(WHEN_WITH_THREE_ARMS).unwrap();
+
+    let Some(Statement::WhenStatement { arms, default, .. }) = ast.get(0) else {
+        panic!("expected a when statement, found {:?}", ast.get(0));
+    };
+    let values = arms.iter().map(|(value, _)| parenthesize(value)).collect::<Vec<_>>();
+    assert_eq!(values, vec!["1", "2", "3"]);
+    assert!(default.is_some());
+}
+
+#[test]
+fn else_branch_of_a_conditional_extends_as_far_as_it_can() {
+    assert_eq!(parenthesized_value("let max be if a > b then a else b + 1"), "(if (a > b) then a else (b + 1))");
+}
+
+#[test]
+fn parenthesized_conditional_is_an_operand() {
+    assert_eq!(parenthesized_value("let doubled be (if a > b then a else b) * 2"), "((if (a > b) then a else b) * 2)");
+}
+
+#[test]
+fn reversed_less_or_equal_suggests_the_operator() {
+    let diagnostics = parse("let smaller be a =< b").err().expect("the program should not parse");
+
+    assert_eq!(diagnostics.diagnostics.iter().map(|d| d.code()).collect::<Vec<_>>(), vec!["E0004"]);
+    assert!(diagnostics.to_string().contains("Unknown operator '=<'. Did you mean '<='?"), "{}", diagnostics);
+}
+
+#[test]
+fn directly_nested_parentheses_give_a_single_group() {
+    let ast = parse("let x be ((1 + 2))").unwrap();
+
+    let Expression::Grouped(inner) = declared_value(&ast) else {
+        panic!("expected a grouped expression, found {:?}", declared_value(&ast));
+    };
+    assert!(matches!(**inner, Expression::BinaryOperation { .. }), "{:?}", inner);
+}
+
+#[test]
+fn declarations_separated_by_a_semicolon_are_two_statements() {
+    let ast = parse("let a be 1; let b be 2").unwrap();
+
+    assert_eq!(ast.statements().len(), 2);
+    assert!(matches!(ast.get(1), Some(Statement::VariableDeclaration { name, .. }) if name.value == "b"));
+}
+
+#[test]
+fn declaration_span_starts_at_let() {
+    let ast = parse("\n    let total be 1 + 2").unwrap();
+
+    let span = ast.get(0).unwrap().span();
+    assert_eq!((span.start.line, span.start.column), (2, 5));
+    assert_eq!((span.end.line, span.end.column), (2, 23));
+}
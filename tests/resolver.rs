@@ -0,0 +1,196 @@
+use navacodelang::{compiler::{CompilationUnit, Compiler, SourceCode}, lexer::{TextSpan, TokenPosition}, types::Type};
+
+fn compile(source: &str) -> CompilationUnit {
+    Compiler::new()
+        .compile(&SourceCode::from_string(source.to_string()))
+        .unwrap_or_else(|diagnostics| panic!("the program should compile:\n{}", diagnostics))
+}
+
+fn error_codes(source: &str) -> Vec<&'static str> {
+    let diagnostics = Compiler::new()
+        .compile(&SourceCode::from_string(source.to_string()))
+        .err()
+        .expect("the program should not compile");
+    diagnostics.diagnostics.iter().filter(|d| d.is_error()).map(|d| d.code()).collect()
+}
+
+fn warning_codes(source: &str) -> Vec<&'static str> {
+    compile(source).warnings.diagnostics.iter().map(|d| d.code()).collect()
+}
+
+// Columns are 1-based and `end` is exclusive, like token spans
+fn span(line: usize, start: usize, end: usize) -> TextSpan {
+    TextSpan {
+        start: TokenPosition { line, column: start },
+        end: TokenPosition { line, column: end },
+    }
+}
+
+#[test]
+fn type_of_an_expression_is_recorded_at_its_span() {
+    let compilation_unit = compile("let sum be 1 + 2\nlet total be sum");
+
+    assert_eq!(compilation_unit.type_map.type_at(&span(1, 12, 17)), Some(&Type::Int));
+}
+
+#[test]
+fn expressions_sharing_a_span_record_the_outermost_type() {
+    // `not (1 < 2)`, `(1 < 2)` and `1 < 2` all span columns 18 to 23
+    let compilation_unit = compile("let flag be not (1 < 2)\nlet copy be flag");
+
+    assert_eq!(compilation_unit.type_map.type_at(&span(1, 18, 23)), Some(&Type::Bool));
+}
+
+#[test]
+fn len_rejects_an_argument_that_is_not_a_list() {
+    assert_eq!(error_codes("let n be len(5)"), vec!["E0021"]);
+}
+
+#[test]
+fn operand_of_a_known_wrong_type_is_reported_next_to_a_parameter() {
+    let codes = error_codes("define function f with x as\n    return (true + x)\nend\nlet r be f(1)");
+
+    assert!(codes.contains(&"E0022"), "{:?}", codes);
+}
+
+#[test]
+fn arithmetic_on_a_parameter_without_annotation_resolves_to_int() {
+    let compilation_unit = compile("define function double with x as\n    return (x * 2)\nend\nlet r be double(4)");
+
+    let double = compilation_unit.symbols_table.lookup_function("double").unwrap();
+    assert_eq!(double.return_type, Type::Int);
+}
+
+#[test]
+fn comparing_a_variable_to_itself_warns() {
+    assert!(warning_codes("let x be 1\nlet same be x == x").contains(&"W0008"));
+}
+
+#[test]
+fn comparing_two_variables_does_not_warn() {
+    assert!(!warning_codes("let x be 1\nlet y be 2\nlet same be x == y").contains(&"W0008"));
+}
+
+#[test]
+fn function_with_an_empty_body_warns() {
+    assert!(warning_codes("define function nothing as\nend\nnothing()").contains(&"W0004"));
+}
+
+#[test]
+fn function_returning_only_inside_an_if_is_reported() {
+    let source = "define function f with n as\n    if n > 0 then\n        return (n)\n    end\nend\nlet r be f(1)";
+
+    assert_eq!(error_codes(source), vec!["E0034"]);
+}
+
+#[test]
+fn function_returning_on_every_branch_compiles() {
+    let compilation_unit = compile("define function f with n as\n    if n > 0 then\n        return (n)\n    else\n        return (-n)\n    end\nend\nlet r be f(1)");
+
+    assert_eq!(compilation_unit.symbols_table.lookup_function("f").unwrap().return_type, Type::Int);
+}
+
+#[test]
+fn mutually_recursive_functions_resolve() {
+    let compilation_unit = compile("
+define function is_even with n as
+    if n == 0 then
+        return (true)
+    end
+    return (is_odd(n - 1))
+end
+
+define function is_odd with n as
+    if n == 0 then
+        return (false)
+    end
+    return (is_even(n - 1))
+end
+
+let even be is_even(10)
+");
+
+    assert_eq!(compilation_unit.symbols_table.lookup_function("is_even").unwrap().return_type, Type::Bool);
+    assert_eq!(compilation_unit.symbols_table.lookup_function("is_odd").unwrap().return_type, Type::Bool);
+}
+
+#[test]
+fn value_of_a_function_that_can_fall_off_the_end_is_not_used_as_an_int() {
+    let source = "define function f with n as\n    if n > 0 then\n        return (n)\n    end\nend\nlet r be f(0) + 1";
+
+    assert!(error_codes(source).contains(&"E0034"));
+}
+
+#[test]
+fn only_statements_skipped_by_main_are_reported() {
+    let codes = warning_codes("let limit be 10\nset limit to 3\n\ndefine function main as\n    let doubled be limit * 2\nend");
+
+    assert_eq!(codes.iter().filter(|code| **code == "W0009").count(), 1, "{:?}", codes);
+}
+
+#[test]
+fn ordering_a_bool_or_a_list_is_reported() {
+    assert_eq!(error_codes("let a be 1 < true"), vec!["E0022"]);
+    assert_eq!(error_codes("let a be [1] < [2]"), vec!["E0022"]);
+}
+
+#[test]
+fn equality_between_different_types_is_reported() {
+    assert_eq!(error_codes("let a be 1 == true"), vec!["E0022"]);
+    compile("let a be [1] == [2]\nlet b be true != false");
+}
+
+#[test]
+fn argument_of_the_wrong_type_for_an_inferred_parameter_is_reported() {
+    let source = "define function is_even with n as\n    return (n % 2 == 0)\nend\nlet r be is_even(true)";
+
+    assert_eq!(error_codes(source), vec!["E0021"]);
+}
+
+#[test]
+fn parameter_type_is_inferred_from_its_use() {
+    let compilation_unit = compile("define function negate with flag as\n    return (not flag)\nend\nlet r be negate(true)");
+
+    assert_eq!(compilation_unit.symbols_table.lookup_function("negate").unwrap().parameter_types, vec![Type::Bool]);
+}
+
+#[test]
+fn assigning_a_bool_to_an_int_variable_is_reported() {
+    assert_eq!(error_codes("let x be 5\nset x to true"), vec!["E0020"]);
+}
+
+#[test]
+fn declaration_with_only_a_type_has_that_type() {
+    let compilation_unit = compile("let count be int\nlet next be count + 1");
+
+    assert_eq!(compilation_unit.type_map.type_at(&span(1, 14, 17)), Some(&Type::Int));
+    assert_eq!(error_codes("let done be bool\nset done to 1"), vec!["E0020"]);
+}
+
+#[test]
+fn when_arm_of_another_type_than_the_subject_is_reported() {
+    compile("let day be 2\nlet kind be 0\nwhen day\n    is 1 then\n        set kind to 10\n    is 2 then\n        set kind to 20\n    is 3 then\n        set kind to 30\n    otherwise\n        set kind to 0\nend");
+
+    let codes = error_codes("let day be 2\nlet kind be 0\nwhen day\n    is 1 then\n        set kind to 10\n    is true then\n        set kind to 20\n    is 3 then\n        set kind to 30\n    otherwise\n        set kind to 0\nend");
+    assert_eq!(codes, vec!["E0021"]);
+}
+
+#[test]
+fn for_loop_with_a_zero_step_warns() {
+    let codes = warning_codes("let total be 0\nfor i from 1 to 5 step 0 do\n    set total to total + i\nend");
+
+    assert_eq!(codes, vec!["W0007"]);
+}
+
+#[test]
+fn operand_of_the_wrong_type_is_reported_at_its_own_span() {
+    let diagnostics = Compiler::new()
+        .compile(&SourceCode::from_string("let flag be true and (1 + 1)".to_string()))
+        .err()
+        .expect("the program should not compile");
+
+    let errors = diagnostics.diagnostics.iter().filter(|d| d.is_error()).collect::<Vec<_>>();
+    assert_eq!(errors.iter().map(|d| d.code()).collect::<Vec<_>>(), vec!["E0021"]);
+    // Only `1 + 1`, at column 23, not the whole `and` at column 13
+    assert!(errors[0].to_string().starts_with("ERROR[E0021]: at 1:23:"), "{}", errors[0]);
+}
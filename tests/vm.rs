@@ -0,0 +1,61 @@
+use navacodelang::{bytecode::Codegen, compiler::{Compiler, SourceCode}, interpreter::{vm::Vm, Interpreter}};
+
+// Runs the program through the tree-walking interpreter and the bytecode VM and checks both end with the same globals
+fn assert_same_globals(source: &str) {
+    let compilation_unit = Compiler::new()
+        .compile(&SourceCode::from_string(source.to_string()))
+        .unwrap_or_else(|diagnostics| panic!("the program should compile:\n{}", diagnostics));
+
+    let mut interpreter = Interpreter::new();
+    interpreter.run_program(&compilation_unit.ast);
+
+    let bytecode = Codegen::generate(&compilation_unit.ast).unwrap_or_else(|error| panic!("{}", error));
+    let mut vm = Vm::new();
+    vm.run(&bytecode).unwrap_or_else(|error| panic!("{}", error));
+
+    assert!(!vm.globals().is_empty());
+    assert_eq!(interpreter.globals(), vm.globals());
+}
+
+#[test]
+fn arithmetic_and_assignments_match_the_interpreter() {
+    assert_same_globals("let a be 7\nconst b be 3\nlet sum be a + b * 2 - -a\nlet rest be a % b\nlet power be 2 ** 3 ** 2\nset a to a / b\nlet bits be (a shl 3) bor 1 bxor 6 band 5 shr 1");
+}
+
+#[test]
+fn comparisons_and_logic_match_the_interpreter() {
+    assert_same_globals("let x be 4\nlet small be x < 5 and not (x == 0)\nlet either be x >= 10 or x != 4\nlet picked be if small then x * 10 else x - 10");
+}
+
+#[test]
+fn swap_and_built_in_calls_match_the_interpreter() {
+    assert_same_globals("let low be 9\nlet high be -2\nswap low and high\nlet clamped be clamp(15, low, high)\nlet signs be sign(low) + sign(high) * 10");
+}
+
+#[test]
+fn runtime_error_carries_the_span_of_the_failing_expression() {
+    let ast = Compiler::new()
+        .compile(&SourceCode::from_string("let x be 1\nlet y be x / 0".to_string()))
+        .unwrap()
+        .ast;
+    let bytecode = Codegen::generate(&ast).unwrap();
+
+    let error = Vm::new().run(&bytecode).unwrap_err();
+
+    assert_eq!(error.to_string(), "at 2:10: division by zero");
+}
+
+#[test]
+fn sample_program_matches_the_interpreter() {
+    assert_same_globals(include_str!("../testing.nvc"));
+}
+
+#[test]
+fn functions_run_in_their_own_frame() {
+    assert_same_globals("let x be 1\ndefine function shadow with x as\n    let y be x * 2\n    return (y + 1)\nend\nlet z be shadow(20)\nif z > 0 then\n    let x be 5\n    set z to z + x\nend");
+}
+
+#[test]
+fn main_runs_after_the_declarations() {
+    assert_same_globals("let limit be 10\nlet total be 0\ndefine function main as\n    set total to limit * 2\nend");
+}